@@ -31,7 +31,7 @@ fn tcp_server_throughput(c: &mut Criterion) {
 
                     server.start().expect("Failed to start server");
                     
-                    let mut reactor = server.get_reactor();
+                    let mut reactor = server.get_reactor().expect("Reactor has already been taken");
                     let server_thread = thread::spawn(move || {
                         reactor.run().expect("Reactor failed");
                     });
@@ -1,3 +1,4 @@
 pub mod reactor;
+pub mod reactor_pool;
 pub mod memory_pool;
 pub mod connection_pool;
\ No newline at end of file
@@ -1,39 +1,211 @@
-use std::collections::HashMap;
-use std::io::{Error, Result};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::io::{Error, ErrorKind, Result};
 use std::os::unix::io::RawFd;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 const MAX_EVENTS : usize = 10;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TimerId(u64);
+
+type TimerCallback = Box<dyn FnMut(&mut Reactor) + Send>;
+
+struct Timer {
+    interval: Option<Duration>,
+    callback: TimerCallback,
+}
+
+type Task = Box<dyn FnOnce(&mut Reactor) + Send>;
+
+type SignalHandler = Box<dyn FnMut(&mut Reactor) + Send>;
+
+struct Signals {
+    fd: RawFd,
+    mask: libc::sigset_t,
+    handlers: HashMap<i32, SignalHandler>,
+    // 正在执行的处理函数，回调中移除自己时置为 None
+    firing: Option<i32>,
+}
+
+struct Wakeup {
+    fd: RawFd,
+}
+
+impl Wakeup {
+    fn new() -> Result<Self> {
+        let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if fd == -1 {
+            return Err(Error::last_os_error());
+        }
+        Ok(Wakeup { fd })
+    }
+
+    fn notify(&self) {
+        let one = 1u64;
+        unsafe {
+            libc::write(
+                self.fd,
+                &one as *const u64 as *const libc::c_void,
+                std::mem::size_of::<u64>(),
+            );
+        }
+    }
+
+    fn drain(&self) {
+        let mut count = 0u64;
+        unsafe {
+            libc::read(
+                self.fd,
+                &mut count as *mut u64 as *mut libc::c_void,
+                std::mem::size_of::<u64>(),
+            );
+        }
+    }
+}
+
+impl Drop for Wakeup {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ReactorHandle {
+    running: Arc<AtomicBool>,
+    stop_requested: Arc<AtomicBool>,
+    wakeup: Arc<Wakeup>,
+    tasks: Arc<Mutex<Vec<Task>>>,
+}
+
+impl ReactorHandle {
+    pub fn post<F>(&self, task: F)
+    where
+        F: FnOnce(&mut Reactor) + Send + 'static,
+    {
+        self.tasks.lock().unwrap().push(Box::new(task));
+        self.wakeup.notify();
+    }
+
+    pub fn stop(&self) {
+        self.stop_requested.store(true, Ordering::SeqCst);
+        self.wakeup.notify();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+}
+
 pub struct Reactor{
     epoll_fd : RawFd,
     running: Arc<AtomicBool>,
+    // 只由 stop() 设置，run() 开始前调用 stop() 也不会丢失
+    stop_requested: Arc<AtomicBool>,
     handlers: HashMap<RawFd, Box<dyn FnMut(u32) + Send>>,
+    timer_fd: RawFd,
+    timers: HashMap<TimerId, Timer>,
+    timer_queue: BinaryHeap<Reverse<(Instant, TimerId)>>,
+    next_timer_id: u64,
+    // 正在执行回调的周期定时器，回调中取消自己时置为 None
+    firing_periodic: Option<TimerId>,
+    wakeup: Arc<Wakeup>,
+    tasks: Arc<Mutex<Vec<Task>>>,
+    signals: Option<Signals>,
 }
 
 impl Reactor{
     pub fn new() -> Result<Self> {
+        let wakeup = Arc::new(Wakeup::new()?);
+
         let epoll_fd = unsafe { libc ::epoll_create1(0)};
         if epoll_fd == -1 {
             return Err(Error::last_os_error());
         }
 
+        let timer_fd = match Self::create_timer_fd(epoll_fd) {
+            Ok(fd) => fd,
+            Err(e) => {
+                unsafe { libc::close(epoll_fd) };
+                return Err(e);
+            }
+        };
+
+        if let Err(e) = Self::register_internal_fd(epoll_fd, wakeup.fd) {
+            unsafe {
+                libc::close(timer_fd);
+                libc::close(epoll_fd);
+            }
+            return Err(e);
+        }
+
         Ok(
             Reactor { 
             epoll_fd, 
             running: Arc::new(AtomicBool::new(false)), 
-            handlers:HashMap::new() 
+            stop_requested: Arc::new(AtomicBool::new(false)),
+            handlers:HashMap::new(),
+            timer_fd,
+            timers: HashMap::new(),
+            timer_queue: BinaryHeap::new(),
+            next_timer_id: 0,
+            firing_periodic: None,
+            wakeup,
+            tasks: Arc::new(Mutex::new(Vec::new())),
+            signals: None,
             }
         )
     }
 
+    fn create_timer_fd(epoll_fd: RawFd) -> Result<RawFd> {
+        let timer_fd = unsafe {
+            libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK | libc::TFD_CLOEXEC)
+        };
+        if timer_fd == -1 {
+            return Err(Error::last_os_error());
+        }
+
+        if let Err(e) = Self::register_internal_fd(epoll_fd, timer_fd) {
+            unsafe { libc::close(timer_fd) };
+            return Err(e);
+        }
+
+        Ok(timer_fd)
+    }
+
+    fn register_internal_fd(epoll_fd: RawFd, fd: RawFd) -> Result<()> {
+        let mut ev = libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: fd as u64,
+        };
+        let result = unsafe {
+            libc::epoll_ctl(epoll_fd, libc::EPOLL_CTL_ADD, fd, &mut ev as *mut libc::epoll_event)
+        };
+        if result == -1 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn handle(&self) -> ReactorHandle {
+        ReactorHandle {
+            running: Arc::clone(&self.running),
+            stop_requested: Arc::clone(&self.stop_requested),
+            wakeup: Arc::clone(&self.wakeup),
+            tasks: Arc::clone(&self.tasks),
+        }
+    }
+
     pub fn add_handler<F>(&mut self, fd: RawFd, events: u32, handler: F) -> Result<()>
     where
         F: FnMut(u32) + Send + 'static,
     {
         let mut ev = libc::epoll_event{
-            events : events as u32,
+            events,
             u64: fd as u64,
         };
 
@@ -55,6 +227,34 @@ impl Reactor{
         Ok(())
     }
 
+    pub fn modify_handler(&mut self, fd: RawFd, events: u32) -> Result<()> {
+        if !self.handlers.contains_key(&fd) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("No handler registered for fd {}", fd),
+            ));
+        }
+
+        let mut ev = libc::epoll_event {
+            events,
+            u64: fd as u64,
+        };
+
+        let result = unsafe {
+            libc::epoll_ctl(
+                self.epoll_fd,
+                libc::EPOLL_CTL_MOD,
+                fd,
+                &mut ev as *mut libc::epoll_event,
+            )
+        };
+
+        if result == -1 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+
     pub fn remove_handler(&mut self, fd: RawFd) -> Result<()> {
         if !self.handlers.contains_key(&fd){
             return Ok(());
@@ -93,6 +293,216 @@ impl Reactor{
         }
     }
 
+    pub fn add_timer<F>(&mut self, delay: Duration, callback: F) -> Result<TimerId>
+    where
+        F: FnMut(&mut Reactor) + Send + 'static,
+    {
+        self.schedule_timer(delay, None, Box::new(callback))
+    }
+
+    pub fn add_periodic<F>(&mut self, interval: Duration, callback: F) -> Result<TimerId>
+    where
+        F: FnMut(&mut Reactor) + Send + 'static,
+    {
+        self.schedule_timer(interval, Some(interval), Box::new(callback))
+    }
+
+    pub fn cancel_timer(&mut self, id: TimerId) -> bool {
+        if self.firing_periodic == Some(id) {
+            self.firing_periodic = None;
+            return true;
+        }
+        // 堆中的过期条目在 process_timers 中惰性丢弃
+        self.timers.remove(&id).is_some()
+    }
+
+    fn schedule_timer(
+        &mut self,
+        delay: Duration,
+        interval: Option<Duration>,
+        callback: TimerCallback,
+    ) -> Result<TimerId> {
+        let id = TimerId(self.next_timer_id);
+        self.next_timer_id += 1;
+
+        self.timers.insert(id, Timer { interval, callback });
+        self.timer_queue.push(Reverse((Instant::now() + delay, id)));
+
+        if let Err(e) = self.arm_timer_fd() {
+            self.timers.remove(&id);
+            return Err(e);
+        }
+        Ok(id)
+    }
+
+    fn arm_timer_fd(&mut self) -> Result<()> {
+        while let Some(Reverse((_, id))) = self.timer_queue.peek() {
+            if self.timers.contains_key(id) {
+                break;
+            }
+            self.timer_queue.pop();
+        }
+
+        let mut spec: libc::itimerspec = unsafe { std::mem::zeroed() };
+        if let Some(Reverse((deadline, _))) = self.timer_queue.peek() {
+            // it_value 为 0 会解除定时器，已到期的至少设置 1ns
+            let delay = deadline
+                .saturating_duration_since(Instant::now())
+                .max(Duration::from_nanos(1));
+            spec.it_value.tv_sec = delay.as_secs() as libc::time_t;
+            spec.it_value.tv_nsec = delay.subsec_nanos() as libc::c_long;
+        }
+
+        let result = unsafe { libc::timerfd_settime(self.timer_fd, 0, &spec, std::ptr::null_mut()) };
+        if result == -1 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn process_timers(&mut self) -> Result<()> {
+        let mut expirations = 0u64;
+        unsafe {
+            libc::read(
+                self.timer_fd,
+                &mut expirations as *mut u64 as *mut libc::c_void,
+                std::mem::size_of::<u64>(),
+            );
+        }
+
+        let now = Instant::now();
+        while let Some(&Reverse((deadline, id))) = self.timer_queue.peek() {
+            if deadline > now {
+                break;
+            }
+            self.timer_queue.pop();
+
+            let mut timer = match self.timers.remove(&id) {
+                Some(timer) => timer,
+                None => continue,
+            };
+            if timer.interval.is_some() {
+                self.firing_periodic = Some(id);
+            }
+            (timer.callback)(self);
+
+            if let Some(interval) = timer.interval {
+                if self.firing_periodic.take() != Some(id) {
+                    continue;
+                }
+                let next = (deadline + interval).max(now);
+                self.timer_queue.push(Reverse((next, id)));
+                self.timers.insert(id, timer);
+            }
+        }
+
+        self.arm_timer_fd()
+    }
+
+    // 信号在调用线程上被屏蔽，改由 signalfd 作为普通事件分发。
+    // 应在创建其它线程之前调用，新线程会继承屏蔽字，否则信号可能被投递到未屏蔽的线程
+    pub fn add_signal_handler<F>(&mut self, signal: i32, handler: F) -> Result<()>
+    where
+        F: FnMut(&mut Reactor) + Send + 'static,
+    {
+        let mut mask = match &self.signals {
+            Some(signals) => signals.mask,
+            None => Self::empty_sigset(),
+        };
+        if unsafe { libc::sigaddset(&mut mask, signal) } == -1 {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("invalid signal {}", signal)));
+        }
+        Self::block_signal(libc::SIG_BLOCK, signal)?;
+
+        match self.signals.as_mut() {
+            Some(signals) => {
+                if unsafe { libc::signalfd(signals.fd, &mask, 0) } == -1 {
+                    return Err(Error::last_os_error());
+                }
+                signals.mask = mask;
+                signals.handlers.insert(signal, Box::new(handler));
+            }
+            None => {
+                let fd = unsafe { libc::signalfd(-1, &mask, libc::SFD_NONBLOCK | libc::SFD_CLOEXEC) };
+                if fd == -1 {
+                    return Err(Error::last_os_error());
+                }
+                if let Err(e) = Self::register_internal_fd(self.epoll_fd, fd) {
+                    unsafe { libc::close(fd) };
+                    return Err(e);
+                }
+                let mut handlers: HashMap<i32, SignalHandler> = HashMap::new();
+                handlers.insert(signal, Box::new(handler));
+                self.signals = Some(Signals { fd, mask, handlers, firing: None });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove_signal_handler(&mut self, signal: i32) -> Result<bool> {
+        let signals = match self.signals.as_mut() {
+            Some(signals) => signals,
+            None => return Ok(false),
+        };
+        // 回调中移除自己时处理函数已经被取出，不在表中
+        let firing = signals.firing == Some(signal);
+        if firing {
+            signals.firing = None;
+        }
+        if signals.handlers.remove(&signal).is_none() && !firing {
+            return Ok(false);
+        }
+
+        unsafe { libc::sigdelset(&mut signals.mask, signal) };
+        if unsafe { libc::signalfd(signals.fd, &signals.mask, 0) } == -1 {
+            return Err(Error::last_os_error());
+        }
+        Self::block_signal(libc::SIG_UNBLOCK, signal)?;
+        Ok(true)
+    }
+
+    fn empty_sigset() -> libc::sigset_t {
+        let mut set: libc::sigset_t = unsafe { std::mem::zeroed() };
+        unsafe { libc::sigemptyset(&mut set) };
+        set
+    }
+
+    fn block_signal(how: i32, signal: i32) -> Result<()> {
+        let mut set = Self::empty_sigset();
+        unsafe { libc::sigaddset(&mut set, signal) };
+        let result = unsafe { libc::pthread_sigmask(how, &set, std::ptr::null_mut()) };
+        if result != 0 {
+            return Err(Error::from_raw_os_error(result));
+        }
+        Ok(())
+    }
+
+    fn process_signals(&mut self) {
+        let mut info: libc::signalfd_siginfo = unsafe { std::mem::zeroed() };
+        let size = std::mem::size_of::<libc::signalfd_siginfo>();
+        while let Some(signals) = self.signals.as_mut() {
+            let n = unsafe { libc::read(signals.fd, &mut info as *mut _ as *mut libc::c_void, size) };
+            if n != size as isize {
+                break;
+            }
+            // 取出后再调用，回调中可以增删信号处理函数
+            let signal = info.ssi_signo as i32;
+            let mut handler = match signals.handlers.remove(&signal) {
+                Some(handler) => handler,
+                None => continue,
+            };
+            signals.firing = Some(signal);
+            handler(self);
+
+            // 回调中移除了自己时不再放回，注册了新的处理函数时以新的为准
+            if let Some(signals) = self.signals.as_mut() {
+                if signals.firing.take() == Some(signal) {
+                    signals.handlers.entry(signal).or_insert(handler);
+                }
+            }
+        }
+    }
+
     pub fn run(&mut self) -> Result<()> {
         let mut events = vec![
             libc::epoll_event { events: 0, u64: 0 };
@@ -100,14 +510,23 @@ impl Reactor{
         ];
 
         self.running.store(true, Ordering::SeqCst);
+        let result = self.run_loop(&mut events);
+        // 只在退出时清除，run 之前调用的 stop 仍然有效，停止后可以再次 run
+        self.stop_requested.store(false, Ordering::SeqCst);
+        self.running.store(false, Ordering::SeqCst);
+        result
+    }
+
+    fn run_loop(&mut self, events: &mut [libc::epoll_event]) -> Result<()> {
+        self.run_pending_tasks();
 
-        while self.running.load(Ordering::SeqCst) {
+        while !self.stop_requested.load(Ordering::SeqCst) {
             let nfds = unsafe {
                 libc::epoll_wait(
                     self.epoll_fd,
                     events.as_mut_ptr(),
                     MAX_EVENTS as i32,
-                    100, 
+                    -1,
                 )
             };
 
@@ -121,47 +540,56 @@ impl Reactor{
 
             for n in 0..nfds {
                 let fd = events[n as usize].u64 as RawFd;
-                if let Some(handler) = self.handlers.get_mut(&fd) {
+                if fd == self.timer_fd {
+                    self.process_timers()?;
+                } else if fd == self.wakeup.fd {
+                    self.wakeup.drain();
+                } else if self.signals.as_ref().is_some_and(|signals| signals.fd == fd) {
+                    self.process_signals();
+                } else if let Some(handler) = self.handlers.get_mut(&fd) {
                     handler(events[n as usize].events);
                 }
             }
+
+            self.run_pending_tasks();
         }
+        // stop 之前投递的任务可能在最后一次检查之后才入队，退出前再执行一次
+        self.run_pending_tasks();
         Ok(())
     }
 
+    fn run_pending_tasks(&mut self) {
+        // 先取出队列再执行，任务中再次 post 不会造成死锁
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        for task in tasks {
+            task(self);
+        }
+    }
+
     pub fn stop(&self) {
         println!("Reactor stopping...");
-        self.running.store(false, Ordering::SeqCst);
+        self.stop_requested.store(true, Ordering::SeqCst);
+        self.wakeup.notify();
     }
 
     pub fn get_epoll_fd(&self) -> RawFd {
         self.epoll_fd
     }
-    
-    pub fn share_running_state(&mut self, running: Arc<AtomicBool>) {
-        self.running = running;
-    }  
-}
-
-impl Clone for Reactor {
-    fn clone(&self) -> Self {
-        let epoll_fd = unsafe { libc::epoll_create1(0) };
-        if epoll_fd == -1 {
-            panic!("Failed to create epoll instance in clone");
-        }
-
-        Self {
-            epoll_fd,
-            running: Arc::clone(&self.running),
-            handlers: HashMap::new(),
-        }
-    }
 }
 
 impl Drop for Reactor {
     fn drop(&mut self) {
         unsafe {
+            libc::close(self.timer_fd);
             libc::close(self.epoll_fd);
+            if let Some(signals) = self.signals.take() {
+                // 先丢弃尚未读取的信号再解除屏蔽，否则它们会按默认动作处理
+                let mut info: libc::signalfd_siginfo = std::mem::zeroed();
+                let size = std::mem::size_of::<libc::signalfd_siginfo>();
+                while libc::read(signals.fd, &mut info as *mut _ as *mut libc::c_void, size) == size as isize {}
+                libc::pthread_sigmask(libc::SIG_UNBLOCK, &signals.mask, std::ptr::null_mut());
+                libc::close(signals.fd);
+            }
         }
     }
 }
\ No newline at end of file
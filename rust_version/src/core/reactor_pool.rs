@@ -0,0 +1,76 @@
+use crate::core::reactor::{Reactor, ReactorHandle};
+use std::io::Result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+
+pub struct ReactorPool {
+    handles: Vec<ReactorHandle>,
+    threads: Mutex<Vec<JoinHandle<Result<()>>>>,
+    round_robin_counter: AtomicUsize,
+}
+
+impl ReactorPool {
+    pub fn new(num_threads: usize) -> Result<Self> {
+        let mut pool = ReactorPool {
+            handles: Vec::with_capacity(num_threads),
+            threads: Mutex::new(Vec::with_capacity(num_threads)),
+            round_robin_counter: AtomicUsize::new(0),
+        };
+
+        for i in 0..num_threads {
+            let mut reactor = Reactor::new()?;
+            let handle = reactor.handle();
+            let thread = thread::Builder::new()
+                .name(format!("reactor-{}", i))
+                .spawn(move || reactor.run())?;
+
+            pool.handles.push(handle);
+            pool.threads.get_mut().unwrap().push(thread);
+        }
+
+        Ok(pool)
+    }
+
+    pub fn next_handle(&self) -> Option<ReactorHandle> {
+        if self.handles.is_empty() {
+            None
+        } else {
+            let index = self.round_robin_counter.fetch_add(1, Ordering::Relaxed) % self.handles.len();
+            Some(self.handles[index].clone())
+        }
+    }
+
+    pub fn handles(&self) -> &[ReactorHandle] {
+        &self.handles
+    }
+
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    pub fn stop(&self) {
+        for handle in &self.handles {
+            handle.stop();
+        }
+
+        let threads = std::mem::take(&mut *self.threads.lock().unwrap());
+        for thread in threads {
+            match thread.join() {
+                Ok(Err(e)) => eprintln!("Reactor thread exited with error: {}", e),
+                Err(_) => eprintln!("Reactor thread panicked"),
+                Ok(Ok(())) => {}
+            }
+        }
+    }
+}
+
+impl Drop for ReactorPool {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
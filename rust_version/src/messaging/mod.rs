@@ -1,2 +1,3 @@
+pub mod rpc;
 mod serializer;
-pub use serializer::{Serializer, Deserializer};
\ No newline at end of file
+pub use serializer::{Deserialize, Deserializer, SerializationError, Serialize, Serializer};
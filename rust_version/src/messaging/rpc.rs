@@ -0,0 +1,283 @@
+use crate::core::reactor::{Reactor, ReactorHandle};
+use crate::messaging::{Deserialize, Deserializer, SerializationError, Serialize, Serializer};
+use crate::network::codec::{Endian, LengthPrefixedCodec, LengthWidth};
+use crate::network::tcp_client::TcpClient;
+use crate::network::tcp_server::TcpServer;
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+pub const HEADER_LEN: usize = 13;
+
+#[derive(Error, Debug)]
+pub enum RpcError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] SerializationError),
+    #[error("Method not found: {0}")]
+    MethodNotFound(String),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Server error: {0}")]
+    Internal(String),
+    #[error("Invalid frame")]
+    InvalidFrame,
+    #[error("Call timed out")]
+    Timeout,
+    #[error("Connection closed")]
+    ConnectionClosed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    MethodNotFound,
+    InvalidRequest,
+    InternalError,
+}
+
+impl Status {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Status::Ok),
+            1 => Some(Status::MethodNotFound),
+            2 => Some(Status::InvalidRequest),
+            3 => Some(Status::InternalError),
+            _ => None,
+        }
+    }
+}
+
+// 帧格式：request_id(u64) method_id(u32) status(u8) 之后是序列化的负载，均为小端
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub request_id: u64,
+    pub method_id: u32,
+    pub status: Status,
+}
+
+impl FrameHeader {
+    pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+        frame.extend_from_slice(&self.request_id.to_le_bytes());
+        frame.extend_from_slice(&self.method_id.to_le_bytes());
+        frame.push(self.status as u8);
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    pub fn decode(frame: &[u8]) -> Result<(FrameHeader, &[u8]), RpcError> {
+        if frame.len() < HEADER_LEN {
+            return Err(RpcError::InvalidFrame);
+        }
+        let request_id = u64::from_le_bytes(frame[0..8].try_into().unwrap());
+        let method_id = u32::from_le_bytes(frame[8..12].try_into().unwrap());
+        let status = Status::from_u8(frame[12]).ok_or(RpcError::InvalidFrame)?;
+        Ok((FrameHeader { request_id, method_id, status }, &frame[HEADER_LEN..]))
+    }
+}
+
+// 方法 id 由名字的 FNV-1a 哈希得到，客户端和服务端无需协商
+pub fn method_id(name: &str) -> u32 {
+    name.bytes()
+        .fold(0x811c9dc5u32, |hash, b| (hash ^ b as u32).wrapping_mul(0x01000193))
+}
+
+fn rpc_codec() -> LengthPrefixedCodec {
+    LengthPrefixedCodec::new(LengthWidth::U32, Endian::Little, MAX_FRAME_SIZE)
+}
+
+fn encode_value<T: Serialize>(value: &T) -> Result<Vec<u8>, SerializationError> {
+    let mut serializer = Serializer::new();
+    serializer.write(value)?;
+    Ok(serializer.data().to_vec())
+}
+
+type MethodHandler = Arc<dyn Fn(&[u8]) -> Result<Vec<u8>, (Status, String)> + Send + Sync>;
+
+pub struct RpcServer {
+    server: TcpServer,
+    methods: Arc<Mutex<HashMap<u32, (String, MethodHandler)>>>,
+}
+
+impl RpcServer {
+    pub fn new(reactor: Reactor, ip: &str, port: u16) -> io::Result<Self> {
+        let mut server = TcpServer::new(reactor, ip, port)?;
+        let methods: Arc<Mutex<HashMap<u32, (String, MethodHandler)>>> = Arc::new(Mutex::new(HashMap::new()));
+
+        server.set_codec(rpc_codec());
+        let dispatch_methods = Arc::clone(&methods);
+        server.set_message_handler(move |conn, frame| {
+            let (header, payload) = match FrameHeader::decode(frame) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    eprintln!("Closing RPC connection {}: {}", conn.id(), e);
+                    conn.close();
+                    return;
+                }
+            };
+
+            let handler = dispatch_methods.lock().unwrap().get(&header.method_id).map(|(_, h)| Arc::clone(h));
+            let result = match handler {
+                Some(handler) => handler(payload),
+                None => Err((Status::MethodNotFound, format!("method id {}", header.method_id))),
+            };
+            let (status, body) = match result {
+                Ok(body) => (Status::Ok, body),
+                Err((status, message)) => (status, encode_value(&message).unwrap_or_default()),
+            };
+
+            let response = FrameHeader { status, ..header }.encode(&body);
+            if let Err(e) = conn.send_frame(&response) {
+                eprintln!("Failed to send RPC response: {}", e);
+            }
+        });
+
+        Ok(RpcServer { server, methods })
+    }
+
+    pub fn register<Req, Resp, F>(&mut self, name: &str, handler: F) -> io::Result<()>
+    where
+        Req: Deserialize,
+        Resp: Serialize,
+        F: Fn(Req) -> Resp + Send + Sync + 'static,
+    {
+        let id = method_id(name);
+        let mut methods = self.methods.lock().unwrap();
+        if let Some((existing, _)) = methods.get(&id) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("method {} conflicts with registered method {}", name, existing),
+            ));
+        }
+
+        let handler: MethodHandler = Arc::new(move |payload: &[u8]| {
+            let request = Deserializer::new(payload)
+                .read::<Req>()
+                .map_err(|e| (Status::InvalidRequest, e.to_string()))?;
+            // 响应序列化失败是服务端的问题
+            encode_value(&handler(request)).map_err(|e| (Status::InternalError, e.to_string()))
+        });
+        methods.insert(id, (name.to_string(), handler));
+        Ok(())
+    }
+
+    // 线程数、超时等选项直接在底层 TcpServer 上设置
+    pub fn server(&mut self) -> &mut TcpServer {
+        &mut self.server
+    }
+
+    pub fn start(&mut self) -> io::Result<()> {
+        self.server.start()
+    }
+
+    pub fn stop(&mut self) -> io::Result<()> {
+        self.server.stop()
+    }
+
+    pub fn get_reactor(&mut self) -> Option<Reactor> {
+        self.server.get_reactor()
+    }
+}
+
+type Reply = Result<(Status, Vec<u8>), RpcError>;
+
+pub struct RpcClient {
+    client: TcpClient,
+    pending: Arc<Mutex<HashMap<u64, mpsc::Sender<Reply>>>>,
+    next_id: AtomicU64,
+}
+
+impl RpcClient {
+    pub fn new(event_loop: ReactorHandle, ip: &str, port: u16) -> io::Result<Self> {
+        let mut client = TcpClient::new(event_loop, ip, port)?;
+        let pending: Arc<Mutex<HashMap<u64, mpsc::Sender<Reply>>>> = Arc::new(Mutex::new(HashMap::new()));
+
+        client.set_codec(rpc_codec());
+        let response_pending = Arc::clone(&pending);
+        client.set_message_handler(move |conn, frame| {
+            let (header, payload) = match FrameHeader::decode(frame) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    eprintln!("Closing RPC connection {}: {}", conn.id(), e);
+                    conn.close();
+                    return;
+                }
+            };
+            // 已超时的调用不再有等待者，响应直接丢弃
+            if let Some(reply) = response_pending.lock().unwrap().remove(&header.request_id) {
+                let _ = reply.send(Ok((header.status, payload.to_vec())));
+            }
+        });
+
+        // 连接断开时所有未完成的调用立即失败
+        let close_pending = Arc::clone(&pending);
+        client.set_close_handler(move |_conn, _reason| {
+            for (_, reply) in close_pending.lock().unwrap().drain() {
+                let _ = reply.send(Err(RpcError::ConnectionClosed));
+            }
+        });
+
+        Ok(RpcClient { client, pending, next_id: AtomicU64::new(1) })
+    }
+
+    pub fn client(&mut self) -> &mut TcpClient {
+        &mut self.client
+    }
+
+    pub fn connect(&self) {
+        self.client.connect();
+    }
+
+    pub fn disconnect(&self) {
+        self.client.disconnect();
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.is_connected()
+    }
+
+    // 阻塞等待响应，多个线程可以同时在一个连接上调用。不能在事件循环线程上调用
+    pub fn call<Req, Resp>(&self, method: &str, request: &Req, timeout: Duration) -> Result<Resp, RpcError>
+    where
+        Req: Serialize,
+        Resp: Deserialize,
+    {
+        let request_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let header = FrameHeader { request_id, method_id: method_id(method), status: Status::Ok };
+        let frame = header.encode(&encode_value(request)?);
+
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.pending.lock().unwrap().insert(request_id, reply_tx);
+        let sent = match self.client.connection() {
+            Some(conn) => conn.send_frame(&frame),
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "client is not connected")),
+        };
+        if let Err(e) = sent {
+            self.pending.lock().unwrap().remove(&request_id);
+            return Err(e.into());
+        }
+
+        let (status, payload) = match reply_rx.recv_timeout(timeout) {
+            Ok(reply) => reply?,
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&request_id);
+                return Err(RpcError::Timeout);
+            }
+        };
+
+        let mut deserializer = Deserializer::new(&payload);
+        match status {
+            Status::Ok => Ok(deserializer.read::<Resp>()?),
+            Status::MethodNotFound => Err(RpcError::MethodNotFound(method.to_string())),
+            Status::InvalidRequest => Err(RpcError::InvalidRequest(deserializer.read::<String>()?)),
+            Status::InternalError => Err(RpcError::Internal(deserializer.read::<String>()?)),
+        }
+    }
+}
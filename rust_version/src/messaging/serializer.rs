@@ -15,6 +15,12 @@ pub struct Serializer {
     buffer: Vec<u8>,
 }
 
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Serializer {
     pub fn new() -> Self {
         Serializer { buffer: Vec::new() }
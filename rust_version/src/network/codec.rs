@@ -0,0 +1,186 @@
+use std::io;
+use std::ops::Range;
+
+pub trait Decoder {
+    // 从 src 开头解析一个完整的帧，返回 (消耗的字节数, 帧内容在 src 中的范围)，数据不足时返回 None
+    fn decode(&mut self, src: &[u8]) -> io::Result<Option<(usize, Range<usize>)>>;
+}
+
+pub trait Encoder {
+    fn encode(&mut self, payload: &[u8], dst: &mut Vec<u8>) -> io::Result<()>;
+}
+
+pub trait Codec: Decoder + Encoder + Send {}
+
+impl<T: Decoder + Encoder + Send> Codec for T {}
+
+fn frame_too_large(len: usize, max: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("frame of {} bytes exceeds limit of {} bytes", len, max),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthWidth {
+    U16,
+    U32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+// 长度字段不包含自身，只表示后面负载的字节数
+#[derive(Debug, Clone)]
+pub struct LengthPrefixedCodec {
+    width: LengthWidth,
+    endian: Endian,
+    max_frame_size: usize,
+}
+
+impl LengthPrefixedCodec {
+    pub fn new(width: LengthWidth, endian: Endian, max_frame_size: usize) -> Self {
+        let limit = match width {
+            LengthWidth::U16 => u16::MAX as usize,
+            LengthWidth::U32 => u32::MAX as usize,
+        };
+        LengthPrefixedCodec { width, endian, max_frame_size: max_frame_size.min(limit) }
+    }
+
+    fn header_len(&self) -> usize {
+        match self.width {
+            LengthWidth::U16 => 2,
+            LengthWidth::U32 => 4,
+        }
+    }
+}
+
+impl Decoder for LengthPrefixedCodec {
+    fn decode(&mut self, src: &[u8]) -> io::Result<Option<(usize, Range<usize>)>> {
+        let header_len = self.header_len();
+        if src.len() < header_len {
+            return Ok(None);
+        }
+
+        let len = match (self.width, self.endian) {
+            (LengthWidth::U16, Endian::Little) => u16::from_le_bytes([src[0], src[1]]) as usize,
+            (LengthWidth::U16, Endian::Big) => u16::from_be_bytes([src[0], src[1]]) as usize,
+            (LengthWidth::U32, Endian::Little) => u32::from_le_bytes([src[0], src[1], src[2], src[3]]) as usize,
+            (LengthWidth::U32, Endian::Big) => u32::from_be_bytes([src[0], src[1], src[2], src[3]]) as usize,
+        };
+        // 长度超限时立即报错，不等待整个帧到达
+        if len > self.max_frame_size {
+            return Err(frame_too_large(len, self.max_frame_size));
+        }
+        if src.len() < header_len + len {
+            return Ok(None);
+        }
+        Ok(Some((header_len + len, header_len..header_len + len)))
+    }
+}
+
+impl Encoder for LengthPrefixedCodec {
+    fn encode(&mut self, payload: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        if payload.len() > self.max_frame_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("frame of {} bytes exceeds limit of {} bytes", payload.len(), self.max_frame_size),
+            ));
+        }
+
+        let len = payload.len();
+        match (self.width, self.endian) {
+            (LengthWidth::U16, Endian::Little) => dst.extend_from_slice(&(len as u16).to_le_bytes()),
+            (LengthWidth::U16, Endian::Big) => dst.extend_from_slice(&(len as u16).to_be_bytes()),
+            (LengthWidth::U32, Endian::Little) => dst.extend_from_slice(&(len as u32).to_le_bytes()),
+            (LengthWidth::U32, Endian::Big) => dst.extend_from_slice(&(len as u32).to_be_bytes()),
+        }
+        dst.extend_from_slice(payload);
+        Ok(())
+    }
+}
+
+// 以 '\n' 分隔，帧内容去掉结尾的 "\r\n" 或 "\n"
+#[derive(Debug, Clone)]
+pub struct LineCodec {
+    max_line_length: usize,
+    // 已经检查过不含换行符的字节数，避免重复扫描
+    scanned: usize,
+}
+
+impl LineCodec {
+    pub fn new(max_line_length: usize) -> Self {
+        LineCodec { max_line_length, scanned: 0 }
+    }
+}
+
+impl Decoder for LineCodec {
+    fn decode(&mut self, src: &[u8]) -> io::Result<Option<(usize, Range<usize>)>> {
+        let scanned = self.scanned.min(src.len());
+        let newline = match src[scanned..].iter().position(|&b| b == b'\n') {
+            Some(pos) => scanned + pos,
+            None => {
+                // 长度限制不含换行符，"\r\n" 还没到齐时最多多出两个字节
+                if src.len() > self.max_line_length.saturating_add(2) {
+                    return Err(frame_too_large(src.len(), self.max_line_length));
+                }
+                self.scanned = src.len();
+                return Ok(None);
+            }
+        };
+
+        self.scanned = 0;
+        let end = if newline > 0 && src[newline - 1] == b'\r' { newline - 1 } else { newline };
+        if end > self.max_line_length {
+            return Err(frame_too_large(end, self.max_line_length));
+        }
+        Ok(Some((newline + 1, 0..end)))
+    }
+}
+
+impl Encoder for LineCodec {
+    fn encode(&mut self, payload: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        if payload.contains(&b'\n') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "line contains a newline"));
+        }
+        dst.extend_from_slice(payload);
+        dst.push(b'\n');
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FixedSizeCodec {
+    frame_size: usize,
+}
+
+impl FixedSizeCodec {
+    pub fn new(frame_size: usize) -> Self {
+        FixedSizeCodec { frame_size: frame_size.max(1) }
+    }
+}
+
+impl Decoder for FixedSizeCodec {
+    fn decode(&mut self, src: &[u8]) -> io::Result<Option<(usize, Range<usize>)>> {
+        if src.len() < self.frame_size {
+            return Ok(None);
+        }
+        Ok(Some((self.frame_size, 0..self.frame_size)))
+    }
+}
+
+impl Encoder for FixedSizeCodec {
+    fn encode(&mut self, payload: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        if payload.len() != self.frame_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("frame must be exactly {} bytes, got {}", self.frame_size, payload.len()),
+            ));
+        }
+        dst.extend_from_slice(payload);
+        Ok(())
+    }
+}
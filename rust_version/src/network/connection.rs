@@ -0,0 +1,1325 @@
+use crate::core::reactor::{ReactorHandle, TimerId};
+use crate::network::codec::Codec;
+use crate::network::proxy_protocol::{self, ParseResult};
+use crate::network::socket;
+#[cfg(feature = "tls")]
+use crate::network::tls;
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, IoSlice};
+use std::net::{IpAddr, SocketAddr};
+use std::fs::File;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+pub type ConnectionId = u64;
+
+pub(crate) type ConnectHandler = Arc<dyn Fn(&Connection) + Send + Sync>;
+pub(crate) type MessageHandler = Arc<dyn Fn(&Connection, &[u8]) + Send + Sync>;
+pub(crate) type CloseHandler = Arc<dyn Fn(&Connection, CloseReason) + Send + Sync>;
+pub(crate) type HighWatermarkHandler = Arc<dyn Fn(&Connection, usize) + Send + Sync>;
+pub(crate) type LowWatermarkHandler = Arc<dyn Fn(&Connection) + Send + Sync>;
+pub(crate) type CodecFactory = Arc<dyn Fn() -> Box<dyn Codec> + Send + Sync>;
+
+const DEFAULT_READ_BUFFER_SIZE: usize = 1024;
+const DEFAULT_HIGH_WATERMARK: usize = 64 * 1024 * 1024;
+const DEFAULT_LOW_WATERMARK: usize = 0;
+// ET 模式或读到 EOF 时每个事件最多连续读取的次数，避免一个连接占住事件循环
+const MAX_READS_PER_EVENT: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    // 对端正常关闭，read 返回 0
+    PeerEof,
+    // 对端关闭写端（EPOLLRDHUP）
+    PeerRdhup,
+    Error(io::ErrorKind),
+    IdleTimeout,
+    // 建立连接后在限定时间内没有收到任何数据
+    FirstByteTimeout,
+    // 输出缓冲区非空但长时间没有写出任何字节
+    WriteStallTimeout,
+    ServerShutdown,
+    // 应用层主动调用 close / close_after_flush
+    Local,
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct Handlers {
+    pub(crate) connect: Option<ConnectHandler>,
+    pub(crate) message: Option<MessageHandler>,
+    pub(crate) close: Option<CloseHandler>,
+    pub(crate) high_watermark: Option<HighWatermarkHandler>,
+    pub(crate) low_watermark: Option<LowWatermarkHandler>,
+    pub(crate) shutdown: Option<ConnectHandler>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct ConnectionOptions {
+    pub(crate) edge_triggered: bool,
+    pub(crate) read_buffer_size: usize,
+    pub(crate) high_watermark: usize,
+    pub(crate) low_watermark: usize,
+    pub(crate) max_connections: usize,
+    pub(crate) max_connections_per_ip: usize,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) first_byte_timeout: Option<Duration>,
+    pub(crate) write_stall_timeout: Option<Duration>,
+    pub(crate) proxy_protocol: bool,
+}
+
+impl ConnectionOptions {
+    // 超时检查周期取最短超时的 1/4，限制在 [10ms, 1s]
+    fn timeout_check_interval(&self) -> Option<Duration> {
+        [self.idle_timeout, self.first_byte_timeout, self.write_stall_timeout]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|timeout| (timeout / 4).clamp(Duration::from_millis(10), Duration::from_secs(1)))
+    }
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        ConnectionOptions {
+            edge_triggered: false,
+            read_buffer_size: DEFAULT_READ_BUFFER_SIZE,
+            high_watermark: DEFAULT_HIGH_WATERMARK,
+            low_watermark: DEFAULT_LOW_WATERMARK,
+            max_connections: usize::MAX,
+            max_connections_per_ip: usize::MAX,
+            idle_timeout: None,
+            first_byte_timeout: None,
+            write_stall_timeout: None,
+            proxy_protocol: false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ConnectionTable {
+    by_fd: HashMap<RawFd, Connection>,
+    per_ip: HashMap<IpAddr, usize>,
+    draining: bool,
+    closed: bool,
+}
+
+// 按套接字的对端地址计数，不受 PROXY 头部影响
+impl ConnectionTable {
+    fn insert(&mut self, conn: Connection) {
+        if let Some(addr) = conn.inner.peer_addr {
+            *self.per_ip.entry(addr.ip()).or_default() += 1;
+        }
+        self.by_fd.insert(conn.fd(), conn);
+    }
+
+    fn remove(&mut self, fd: RawFd) {
+        let addr = match self.by_fd.remove(&fd).and_then(|conn| conn.inner.peer_addr) {
+            Some(addr) => addr,
+            None => return,
+        };
+        if let Some(count) = self.per_ip.get_mut(&addr.ip()) {
+            *count -= 1;
+            if *count == 0 {
+                self.per_ip.remove(&addr.ip());
+            }
+        }
+    }
+}
+
+pub(crate) struct ConnectionRegistry {
+    pub(crate) handlers: Mutex<Handlers>,
+    pub(crate) options: Mutex<ConnectionOptions>,
+    pub(crate) codec: Mutex<Option<CodecFactory>>,
+    #[cfg(feature = "tls")]
+    pub(crate) tls: Mutex<Option<Arc<rustls::ServerConfig>>>,
+    connections: Mutex<ConnectionTable>,
+    groups: Mutex<HashMap<String, HashMap<ConnectionId, Connection>>>,
+    drained: Condvar,
+    next_id: AtomicU64,
+}
+
+impl ConnectionRegistry {
+    pub(crate) fn new() -> Self {
+        ConnectionRegistry {
+            handlers: Mutex::new(Handlers::default()),
+            options: Mutex::new(ConnectionOptions::default()),
+            codec: Mutex::new(None),
+            #[cfg(feature = "tls")]
+            tls: Mutex::new(None),
+            connections: Mutex::new(ConnectionTable::default()),
+            groups: Mutex::new(HashMap::new()),
+            drained: Condvar::new(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub(crate) fn get(&self, fd: RawFd) -> Option<Connection> {
+        self.connections.lock().unwrap().by_fd.get(&fd).cloned()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.connections.lock().unwrap().by_fd.len()
+    }
+
+    pub(crate) fn connections_from(&self, ip: IpAddr) -> usize {
+        self.connections.lock().unwrap().per_ip.get(&ip).copied().unwrap_or(0)
+    }
+
+    // 之后 register 一律失败，已接受但尚未注册完成的连接也在表中，会一起关闭
+    pub(crate) fn close_all(&self, reason: CloseReason) {
+        let connections: Vec<Connection> = {
+            let mut table = self.connections.lock().unwrap();
+            table.closed = true;
+            table.by_fd.values().cloned().collect()
+        };
+        for conn in connections {
+            conn.close_with(reason);
+        }
+    }
+
+    // 停止接收新连接，通知所有连接进入排空状态
+    pub(crate) fn drain_all(&self) {
+        let connections: Vec<Connection> = {
+            let mut table = self.connections.lock().unwrap();
+            table.draining = true;
+            table.by_fd.values().cloned().collect()
+        };
+        for conn in connections {
+            conn.drain();
+        }
+    }
+
+    // 等待所有连接关闭，超时返回 false
+    pub(crate) fn wait_drained(&self, timeout: Duration) -> bool {
+        let table = self.connections.lock().unwrap();
+        let (table, _) = self
+            .drained
+            .wait_timeout_while(table, timeout, |table| !table.by_fd.is_empty())
+            .unwrap();
+        table.by_fd.is_empty()
+    }
+
+    fn remove(&self, fd: RawFd) {
+        self.connections.lock().unwrap().remove(fd);
+        self.drained.notify_all();
+    }
+
+    fn join(&self, conn: &Connection, group: &str) -> io::Result<()> {
+        let mut groups = self.groups.lock().unwrap();
+        // close 先标记 closed 再清理分组，在分组锁内检查就不会留下已关闭的连接
+        if conn.is_closed() {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "connection is closed"));
+        }
+        groups.entry(group.to_string()).or_default().insert(conn.id(), conn.clone());
+        conn.inner.groups.lock().unwrap().insert(group.to_string());
+        Ok(())
+    }
+
+    fn leave(&self, conn: &Connection, group: &str) -> bool {
+        let mut groups = self.groups.lock().unwrap();
+        conn.inner.groups.lock().unwrap().remove(group);
+        Self::remove_member(&mut groups, group, conn.id())
+    }
+
+    fn leave_all(&self, conn: &Connection) {
+        let mut groups = self.groups.lock().unwrap();
+        for group in conn.inner.groups.lock().unwrap().drain() {
+            Self::remove_member(&mut groups, &group, conn.id());
+        }
+    }
+
+    fn remove_member(groups: &mut HashMap<String, HashMap<ConnectionId, Connection>>, group: &str, id: ConnectionId) -> bool {
+        let members = match groups.get_mut(group) {
+            Some(members) => members,
+            None => return false,
+        };
+        let removed = members.remove(&id).is_some();
+        if members.is_empty() {
+            groups.remove(group);
+        }
+        removed
+    }
+
+    // 所有成员共享同一块数据，返回成功发送的连接数
+    pub(crate) fn broadcast(&self, group: &str, data: &[u8], exclude: Option<ConnectionId>) -> usize {
+        let members: Vec<Connection> = match self.groups.lock().unwrap().get(group) {
+            Some(members) => members.values().filter(|conn| Some(conn.id()) != exclude).cloned().collect(),
+            None => return 0,
+        };
+        let chunk: Arc<[u8]> = Arc::from(data);
+        members.iter().filter(|conn| conn.send_shared(&chunk).is_ok()).count()
+    }
+
+    pub(crate) fn group_size(&self, group: &str) -> usize {
+        self.groups.lock().unwrap().get(group).map_or(0, HashMap::len)
+    }
+
+    fn handlers(&self) -> Handlers {
+        self.handlers.lock().unwrap().clone()
+    }
+
+    pub(crate) fn register(
+        self: &Arc<Self>,
+        fd: RawFd,
+        peer_addr: Option<SocketAddr>,
+        local_addr: Option<SocketAddr>,
+        event_loop: ReactorHandle,
+    ) -> io::Result<Connection> {
+        let options = *self.options.lock().unwrap();
+        let mut interest = (libc::EPOLLIN | libc::EPOLLRDHUP) as u32;
+        if options.edge_triggered {
+            interest |= libc::EPOLLET as u32;
+        }
+
+        #[cfg(feature = "tls")]
+        let tls = match self.tls.lock().unwrap().clone() {
+            Some(config) => Some(Box::new(tls::accept(config)?)),
+            None => None,
+        };
+
+        // 检查和插入在同一把锁内完成，多个 accept 线程并发时也不会超出上限
+        let mut table = self.connections.lock().unwrap();
+        if table.closed {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "server is stopped"));
+        }
+        if table.draining {
+            return Err(io::Error::other("server is shutting down"));
+        }
+        if table.by_fd.len() >= options.max_connections {
+            return Err(io::Error::other("connection limit reached"));
+        }
+        if let Some(addr) = peer_addr {
+            if table.per_ip.get(&addr.ip()).copied().unwrap_or(0) >= options.max_connections_per_ip {
+                return Err(io::Error::other(format!("connection limit reached for {}", addr.ip())));
+            }
+        }
+
+        // 解码和编码各用一个实例，回调中调用 send_frame 不会和解码冲突
+        let codec = self.codec.lock().unwrap().clone();
+        let framing = codec.as_ref().map(|factory| Framing { decoder: factory(), input: Vec::new() });
+        let encoder = codec.map(|factory| factory());
+
+        let now = Instant::now();
+        let conn = Connection {
+            inner: Arc::new(ConnectionInner {
+                id: self.next_id.fetch_add(1, Ordering::Relaxed),
+                fd,
+                peer_addr,
+                local_addr,
+                options,
+                event_loop: event_loop.clone(),
+                registry: Arc::clone(self),
+                state: Mutex::new(ConnectionState {
+                    interest,
+                    output: OutputBuffer::default(),
+                    above_high_watermark: false,
+                    close_after_flush: false,
+                    peer_shutdown: None,
+                    draining: false,
+                    write_shutdown: false,
+                    closed: false,
+                    created: now,
+                    received_any: false,
+                    last_activity: now,
+                    last_write_progress: now,
+                    timeout_timer: None,
+                    proxy_header: options.proxy_protocol.then(Vec::new),
+                    proxied_peer: None,
+                    #[cfg(feature = "tls")]
+                    tls,
+                }),
+                user_data: Mutex::new(None),
+                groups: Mutex::new(HashSet::new()),
+                framing: Mutex::new(framing),
+                encoder: Mutex::new(encoder),
+            }),
+        };
+        table.insert(conn.clone());
+        drop(table);
+
+        let registered = conn.clone();
+        event_loop.post(move |reactor| {
+            let handler_conn = registered.clone();
+            let mut buffer = Vec::with_capacity(options.read_buffer_size);
+            let result = reactor.add_handler(fd, interest, move |events| {
+                handler_conn.handle_events(events, &mut buffer);
+            });
+
+            match result {
+                Ok(()) => {
+                    // 注册任务执行前已经关闭，由之后的关闭任务移除 handler
+                    if registered.is_closed() {
+                        return;
+                    }
+                    // 超时在连接所属的事件循环线程上检查，关闭时取消定时器
+                    if let Some(interval) = options.timeout_check_interval() {
+                        let timer_conn = registered.clone();
+                        match reactor.add_periodic(interval, move |_| timer_conn.check_timeouts()) {
+                            Ok(id) => {
+                                // close_with 在同一把锁内取走定时器，这里晚于它时自己取消
+                                let mut state = registered.inner.state.lock().unwrap();
+                                if state.closed {
+                                    drop(state);
+                                    reactor.cancel_timer(id);
+                                    return;
+                                }
+                                state.timeout_timer = Some(id);
+                            }
+                            Err(e) => eprintln!("Failed to add timeout timer for fd {}: {}", fd, e),
+                        }
+                    }
+                    // TLS 连接在握手完成后才通知
+                    let handshaking = registered.inner.state.lock().unwrap().handshaking();
+                    if !handshaking {
+                        if let Some(handler) = registered.inner.registry.handlers().connect {
+                            handler(&registered);
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to register connection {}: {}", fd, e);
+                    registered.inner.state.lock().unwrap().closed = true;
+                    registered.inner.registry.remove(fd);
+                    unsafe {
+                        libc::close(fd);
+                    }
+                }
+            }
+        });
+
+        Ok(conn)
+    }
+}
+
+enum Chunk {
+    Data(Arc<[u8]>),
+    // 由 sendfile 直接从文件发送，offset 和 remaining 随发送推进
+    File { file: File, offset: u64, remaining: u64 },
+}
+
+// 单次 sendmsg 最多合并的缓冲区个数，远小于 IOV_MAX
+const MAX_IOVECS: usize = 64;
+
+#[derive(Default)]
+struct OutputBuffer {
+    chunks: VecDeque<Chunk>,
+    offset: usize,
+    // 内存中待发送的字节数，水位线只看这一部分
+    len: usize,
+    file_bytes: u64,
+}
+
+impl OutputBuffer {
+    fn push(&mut self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        self.chunks.push_back(Chunk::Data(Arc::from(data)));
+        self.len += data.len();
+    }
+
+    // 共享的块从 offset 开始发送，只有缓冲区为空时才可能已经直接写出了一部分
+    fn push_shared(&mut self, chunk: Arc<[u8]>, offset: usize) {
+        if offset >= chunk.len() {
+            return;
+        }
+        if self.chunks.is_empty() {
+            self.offset = offset;
+        }
+        self.len += chunk.len() - offset;
+        self.chunks.push_back(Chunk::Data(chunk));
+    }
+
+    fn push_file(&mut self, file: File, offset: u64, len: u64) {
+        if len == 0 {
+            return;
+        }
+        self.file_bytes += len;
+        self.chunks.push_back(Chunk::File { file, offset, remaining: len });
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn pending(&self) -> usize {
+        self.len + self.file_bytes as usize
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    // 写到 EAGAIN 或缓冲区清空为止，返回本次写出的字节数
+    fn write_to(&mut self, fd: RawFd) -> io::Result<usize> {
+        let mut written = 0;
+        while let Some(front) = self.chunks.front() {
+            let result = match front {
+                Chunk::File { file, offset, remaining } => sendfile_raw(fd, file, *offset, *remaining),
+                // 相邻的内存块合并成一次 sendmsg 发送
+                Chunk::Data(_) => {
+                    let slices: Vec<IoSlice> = self
+                        .chunks
+                        .iter()
+                        .take(MAX_IOVECS)
+                        .map_while(|chunk| match chunk {
+                            Chunk::Data(data) => Some(data),
+                            Chunk::File { .. } => None,
+                        })
+                        .enumerate()
+                        .map(|(i, data)| IoSlice::new(if i == 0 { &data[self.offset..] } else { &data[..] }))
+                        .collect();
+                    send_vectored_raw(fd, &slices)
+                }
+            };
+            let n = match result {
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            };
+
+            written += n;
+            self.consume(n);
+        }
+        Ok(written)
+    }
+
+    fn consume(&mut self, mut n: usize) {
+        while n > 0 {
+            let done = match self.chunks.front_mut() {
+                Some(Chunk::Data(data)) => {
+                    let step = n.min(data.len() - self.offset);
+                    n -= step;
+                    self.len -= step;
+                    self.offset += step;
+                    self.offset == data.len()
+                }
+                Some(Chunk::File { offset, remaining, .. }) => {
+                    *offset += n as u64;
+                    *remaining -= n as u64;
+                    self.file_bytes -= n as u64;
+                    n = 0;
+                    *remaining == 0
+                }
+                None => break,
+            };
+            if done {
+                self.chunks.pop_front();
+                self.offset = 0;
+            }
+        }
+    }
+}
+
+struct ConnectionState {
+    interest: u32,
+    output: OutputBuffer,
+    above_high_watermark: bool,
+    close_after_flush: bool,
+    // 对端已半关闭，输出缓冲区写完后以此原因关闭
+    peer_shutdown: Option<CloseReason>,
+    draining: bool,
+    write_shutdown: bool,
+    closed: bool,
+    created: Instant,
+    received_any: bool,
+    last_activity: Instant,
+    last_write_progress: Instant,
+    timeout_timer: Option<TimerId>,
+    // 等待 PROXY 头部时缓存已收到的字节
+    proxy_header: Option<Vec<u8>>,
+    proxied_peer: Option<SocketAddr>,
+    #[cfg(feature = "tls")]
+    tls: Option<Box<rustls::ServerConnection>>,
+}
+
+impl ConnectionState {
+    fn handshaking(&self) -> bool {
+        if self.proxy_header.is_some() {
+            return true;
+        }
+        #[cfg(feature = "tls")]
+        if let Some(tls) = &self.tls {
+            return tls.is_handshaking();
+        }
+        false
+    }
+}
+
+enum ReadResult {
+    Done,
+    Eof,
+    // 达到单次事件的读取上限，剩余数据留到下一轮
+    Limited,
+}
+
+struct Framing {
+    decoder: Box<dyn Codec>,
+    input: Vec<u8>,
+}
+
+struct ConnectionInner {
+    id: ConnectionId,
+    fd: RawFd,
+    peer_addr: Option<SocketAddr>,
+    local_addr: Option<SocketAddr>,
+    options: ConnectionOptions,
+    event_loop: ReactorHandle,
+    registry: Arc<ConnectionRegistry>,
+    state: Mutex<ConnectionState>,
+    user_data: Mutex<Option<Box<dyn Any + Send>>>,
+    groups: Mutex<HashSet<String>>,
+    framing: Mutex<Option<Framing>>,
+    encoder: Mutex<Option<Box<dyn Codec>>>,
+}
+
+#[derive(Clone)]
+pub struct Connection {
+    inner: Arc<ConnectionInner>,
+}
+
+// writev 没有 MSG_NOSIGNAL，用 sendmsg 代替
+fn send_vectored_raw(fd: RawFd, bufs: &[IoSlice]) -> io::Result<usize> {
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    // IoSlice 在 unix 上与 iovec 的内存布局相同
+    msg.msg_iov = bufs.as_ptr() as *mut libc::iovec;
+    msg.msg_iovlen = bufs.len().min(MAX_IOVECS) as _;
+    loop {
+        let sent = unsafe { libc::sendmsg(fd, &msg, libc::MSG_NOSIGNAL) };
+        if sent >= 0 {
+            return Ok(sent as usize);
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+fn sendfile_raw(fd: RawFd, file: &File, offset: u64, remaining: u64) -> io::Result<usize> {
+    // 单次 sendfile 最多传输 0x7ffff000 字节
+    let count = remaining.min(0x7fff_f000) as usize;
+    loop {
+        let mut off = offset as libc::off_t;
+        let sent = unsafe { libc::sendfile(fd, file.as_raw_fd(), &mut off, count) };
+        match sent {
+            0 => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "file is shorter than requested")),
+            n if n > 0 => return Ok(n as usize),
+            _ => {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+// 每次最多读 chunk_size 字节，单个事件读取的总量才有上限
+fn read_some(fd: RawFd, buffer: &mut Vec<u8>, chunk_size: usize) -> io::Result<usize> {
+    buffer.reserve(chunk_size);
+    let spare = (buffer.capacity() - buffer.len()).min(chunk_size);
+
+    let bytes_read = unsafe {
+        libc::read(
+            fd,
+            buffer.as_mut_ptr().add(buffer.len()) as *mut libc::c_void,
+            spare,
+        )
+    };
+    if bytes_read == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    unsafe {
+        buffer.set_len(buffer.len() + bytes_read as usize);
+    }
+    Ok(bytes_read as usize)
+}
+
+impl Connection {
+    pub fn id(&self) -> ConnectionId {
+        self.inner.id
+    }
+
+    pub fn fd(&self) -> RawFd {
+        self.inner.fd
+    }
+
+    // 启用 PROXY protocol 时返回头部中的客户端地址
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.inner.state.lock().unwrap().proxied_peer.or(self.inner.peer_addr)
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.inner.local_addr
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.state.lock().unwrap().closed
+    }
+
+    pub fn set_user_data<T: Any + Send>(&self, data: T) {
+        *self.inner.user_data.lock().unwrap() = Some(Box::new(data));
+    }
+
+    pub fn with_user_data<T: Any + Send, R, F: FnOnce(&mut T) -> R>(&self, f: F) -> Option<R> {
+        let mut user_data = self.inner.user_data.lock().unwrap();
+        user_data.as_mut().and_then(|data| data.downcast_mut::<T>()).map(f)
+    }
+
+    pub fn take_user_data<T: Any + Send>(&self) -> Option<T> {
+        let mut user_data = self.inner.user_data.lock().unwrap();
+        match user_data.take().map(|data| data.downcast::<T>()) {
+            Some(Ok(data)) => Some(*data),
+            Some(Err(data)) => {
+                *user_data = Some(data);
+                None
+            }
+            None => None,
+        }
+    }
+
+    #[cfg(feature = "tls")]
+    pub fn tls_server_name(&self) -> Option<String> {
+        let state = self.inner.state.lock().unwrap();
+        state.tls.as_ref().and_then(|tls| tls.server_name()).map(str::to_string)
+    }
+
+    pub fn send(&self, data: &[u8]) -> io::Result<()> {
+        self.send_chunk(&[IoSlice::new(data)], None)
+    }
+
+    // 多个连接共享同一块数据，写不完的部分直接引用它而不是复制
+    pub fn send_shared(&self, data: &Arc<[u8]>) -> io::Result<()> {
+        self.send_chunk(&[IoSlice::new(data)], Some(data))
+    }
+
+    // 头部、正文等分散的片段用一次 writev 发出，不必先拼接
+    pub fn send_vectored(&self, bufs: &[IoSlice]) -> io::Result<()> {
+        self.send_chunk(bufs, None)
+    }
+
+    fn send_chunk(&self, bufs: &[IoSlice], shared: Option<&Arc<[u8]>>) -> io::Result<()> {
+        let crossed = {
+            let mut state = self.inner.state.lock().unwrap();
+            if state.closed || state.close_after_flush || state.write_shutdown {
+                return Err(io::Error::new(io::ErrorKind::NotConnected, "connection is closing"));
+            }
+
+            #[cfg(feature = "tls")]
+            let sealed = match state.tls.as_mut() {
+                Some(tls) => Some(tls::seal(tls, bufs)?),
+                None => None,
+            };
+            // TLS 连接的密文各不相同，无法共享
+            #[cfg(feature = "tls")]
+            let sealed_bufs = sealed.as_ref().map(|sealed| [IoSlice::new(sealed)]);
+            #[cfg(feature = "tls")]
+            let (bufs, shared) = match &sealed_bufs {
+                Some(sealed) => (&sealed[..], None),
+                None => (bufs, shared),
+            };
+
+            if let Err(e) = self.queue_output(&mut state, bufs, shared) {
+                drop(state);
+                self.close_with(CloseReason::Error(e.kind()));
+                return Err(e);
+            }
+
+            let crossed = !state.above_high_watermark
+                && state.output.len() >= self.inner.options.high_watermark;
+            if crossed {
+                state.above_high_watermark = true;
+            }
+            crossed.then_some(state.output.len())
+        };
+
+        if let Some(buffered) = crossed {
+            if let Some(handler) = self.inner.registry.handlers().high_watermark {
+                handler(self, buffered);
+            }
+        }
+        Ok(())
+    }
+
+    // 输出缓冲区为空时直接写，避免额外的一次 EPOLLOUT 唤醒
+    fn queue_output(&self, state: &mut ConnectionState, bufs: &[IoSlice], shared: Option<&Arc<[u8]>>) -> io::Result<()> {
+        let total: usize = bufs.iter().map(|buf| buf.len()).sum();
+        let mut written = 0;
+        if state.output.is_empty() && total > 0 {
+            written = match send_vectored_raw(self.inner.fd, bufs) {
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => 0,
+                Err(e) => return Err(e),
+            };
+        }
+
+        if written > 0 {
+            state.last_activity = Instant::now();
+        }
+        if written < total {
+            // 输出缓冲区从空变为非空时开始计算写停滞时间
+            if state.output.is_empty() {
+                state.last_write_progress = Instant::now();
+            }
+            match shared {
+                Some(chunk) => state.output.push_shared(Arc::clone(chunk), written),
+                None => {
+                    // 剩余部分合并成一块，之后按普通数据发送
+                    let mut remaining = Vec::with_capacity(total - written);
+                    let mut skip = written;
+                    for buf in bufs {
+                        let start = skip.min(buf.len());
+                        skip -= start;
+                        remaining.extend_from_slice(&buf[start..]);
+                    }
+                    state.output.push(&remaining);
+                }
+            }
+            let interest = state.interest | libc::EPOLLOUT as u32;
+            self.set_interest(state, interest);
+        }
+        Ok(())
+    }
+
+    pub fn join_group(&self, group: &str) -> io::Result<()> {
+        self.inner.registry.join(self, group)
+    }
+
+    pub fn leave_group(&self, group: &str) -> bool {
+        self.inner.registry.leave(self, group)
+    }
+
+    pub fn groups(&self) -> Vec<String> {
+        self.inner.groups.lock().unwrap().iter().cloned().collect()
+    }
+
+    // 发送给分组内除自己以外的所有连接
+    pub fn broadcast(&self, group: &str, data: &[u8]) -> usize {
+        self.inner.registry.broadcast(group, data, Some(self.id()))
+    }
+
+    // 设置了 codec 时先编码成帧再发送，否则等同于 send
+    pub fn send_frame(&self, payload: &[u8]) -> io::Result<()> {
+        let mut frame = Vec::new();
+        match self.inner.encoder.lock().unwrap().as_mut() {
+            Some(encoder) => encoder.encode(payload, &mut frame)?,
+            None => return self.send(payload),
+        }
+        self.send(&frame)
+    }
+
+    // 包括尚未发送的文件字节
+    pub fn pending_bytes(&self) -> usize {
+        self.inner.state.lock().unwrap().output.pending()
+    }
+
+    // 发送文件的 [offset, offset + len) 区间，数据由内核直接从文件拷贝到套接字。
+    // 和 send 的数据按调用顺序发送，发送完成前文件由连接持有
+    pub fn send_file(&self, file: File, offset: u64, len: u64) -> io::Result<()> {
+        let size = file.metadata()?.len();
+        if offset.checked_add(len).is_none_or(|end| end > size) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "range exceeds file size"));
+        }
+
+        #[cfg(feature = "tls")]
+        if self.inner.state.lock().unwrap().tls.is_some() {
+            return self.send_file_copied(&file, offset, len);
+        }
+
+        let mut state = self.inner.state.lock().unwrap();
+        if state.closed || state.close_after_flush || state.write_shutdown {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "connection is closing"));
+        }
+
+        let was_empty = state.output.is_empty();
+        state.output.push_file(file, offset, len);
+        // 缓冲区原本为空时直接发送，和 send 一样避免额外的一次 EPOLLOUT 唤醒
+        if was_empty {
+            match state.output.write_to(self.inner.fd) {
+                Ok(0) => {}
+                Ok(_) => state.last_activity = Instant::now(),
+                Err(e) => {
+                    drop(state);
+                    self.close_with(CloseReason::Error(e.kind()));
+                    return Err(e);
+                }
+            }
+            if !state.output.is_empty() {
+                state.last_write_progress = Instant::now();
+            }
+        }
+        if !state.output.is_empty() {
+            let interest = state.interest | libc::EPOLLOUT as u32;
+            self.set_interest(&mut state, interest);
+        }
+        Ok(())
+    }
+
+    // TLS 需要在用户态加密，只能读出文件内容再发送
+    #[cfg(feature = "tls")]
+    fn send_file_copied(&self, file: &File, offset: u64, len: u64) -> io::Result<()> {
+        use std::os::unix::fs::FileExt;
+
+        let mut buffer = vec![0u8; (len as usize).min(64 * 1024)];
+        let mut sent = 0;
+        while sent < len {
+            let n = buffer.len().min((len - sent) as usize);
+            file.read_exact_at(&mut buffer[..n], offset + sent)?;
+            self.send(&buffer[..n])?;
+            sent += n as u64;
+        }
+        Ok(())
+    }
+
+    // 暂停期间不关注 EPOLLRDHUP，对端半关闭后 resume_read 会先读完剩余数据
+    pub fn pause_read(&self) -> io::Result<()> {
+        self.update_interest(|interest| interest & !((libc::EPOLLIN | libc::EPOLLRDHUP) as u32))
+    }
+
+    pub fn resume_read(&self) -> io::Result<()> {
+        self.update_interest(|interest| interest | (libc::EPOLLIN | libc::EPOLLRDHUP) as u32)
+    }
+
+    fn update_interest<F: FnOnce(u32) -> u32>(&self, update: F) -> io::Result<()> {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.closed {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "connection is closed"));
+        }
+        let interest = update(state.interest);
+        self.set_interest(&mut state, interest);
+        Ok(())
+    }
+
+    fn set_interest(&self, state: &mut ConnectionState, interest: u32) {
+        if state.interest == interest {
+            return;
+        }
+        state.interest = interest;
+        self.post_interest(interest);
+    }
+
+    // ET 模式下 EPOLL_CTL_MOD 会重新检查就绪状态，仍有数据可读时再次触发事件
+    fn rearm_read(&self) {
+        let state = self.inner.state.lock().unwrap();
+        if !state.closed {
+            self.post_interest(state.interest);
+        }
+    }
+
+    // 调用方持有状态锁，投递的任务排在之后的关闭任务之前
+    fn post_interest(&self, interest: u32) {
+        let fd = self.inner.fd;
+        self.inner.event_loop.post(move |reactor| {
+            if let Err(e) = reactor.modify_handler(fd, interest) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    eprintln!("Error modifying events for fd {}: {}", fd, e);
+                }
+            }
+        });
+    }
+
+    pub fn close_after_flush(&self) {
+        let flushed = {
+            let mut state = self.inner.state.lock().unwrap();
+            state.close_after_flush = true;
+            state.output.is_empty()
+        };
+        if flushed {
+            self.close_with(CloseReason::Local);
+        }
+    }
+
+    pub fn close(&self) {
+        self.close_with(CloseReason::Local);
+    }
+
+    pub(crate) fn close_with(&self, reason: CloseReason) {
+        let (timer, reason) = {
+            let mut state = self.inner.state.lock().unwrap();
+            if state.closed {
+                return;
+            }
+            #[cfg(feature = "tls")]
+            self.send_close_notify(&mut state);
+            state.closed = true;
+            // 排空期间对端响应半关闭而断开，仍视为服务器关闭
+            let reason = match reason {
+                CloseReason::PeerEof | CloseReason::PeerRdhup if state.draining => CloseReason::ServerShutdown,
+                reason => reason,
+            };
+            (state.timeout_timer.take(), reason)
+        };
+
+        let fd = self.inner.fd;
+        self.inner.registry.remove(fd);
+        self.inner.registry.leave_all(self);
+
+        // 在事件循环线程上先从 epoll 移除再关闭，避免 fd 被复用后误删新连接
+        let conn = self.clone();
+        self.inner.event_loop.post(move |reactor| {
+            if let Err(e) = reactor.remove_handler(fd) {
+                eprintln!("Error removing handler for fd {}: {}", fd, e);
+            }
+            if let Some(timer) = timer {
+                reactor.cancel_timer(timer);
+            }
+            if let Some(handler) = conn.inner.registry.handlers().close {
+                handler(&conn, reason);
+            }
+            unsafe {
+                if libc::close(fd) == -1 {
+                    eprintln!("Error closing connection: {}", io::Error::last_os_error());
+                }
+            }
+        });
+    }
+
+    // 在事件循环线程上通知应用，输出缓冲区写完后半关闭写端，等待对端关闭
+    pub(crate) fn drain(&self) {
+        let conn = self.clone();
+        self.inner.event_loop.post(move |_reactor| {
+            if conn.is_closed() {
+                return;
+            }
+            if let Some(handler) = conn.inner.registry.handlers().shutdown {
+                handler(&conn);
+            }
+            let mut state = conn.inner.state.lock().unwrap();
+            state.draining = true;
+            if state.output.is_empty() {
+                conn.shutdown_write(&mut state);
+            }
+        });
+    }
+
+    fn shutdown_write(&self, state: &mut ConnectionState) {
+        if state.closed || state.write_shutdown {
+            return;
+        }
+        #[cfg(feature = "tls")]
+        self.send_close_notify(state);
+        state.write_shutdown = true;
+        if unsafe { libc::shutdown(self.inner.fd, libc::SHUT_WR) } == -1 {
+            eprintln!("Error shutting down fd {}: {}", self.inner.fd, io::Error::last_os_error());
+        }
+    }
+
+    // 解析 PROXY 头部，buffer 被替换为头部之后的数据，头部不完整时 buffer 为空
+    fn handle_proxy_header(&self, buffer: &mut Vec<u8>) -> io::Result<()> {
+        let handshaking = {
+            let mut state = self.inner.state.lock().unwrap();
+            let pending = match state.proxy_header.as_mut() {
+                Some(pending) => pending,
+                None => return Ok(()),
+            };
+            pending.append(buffer);
+
+            match proxy_protocol::parse(pending)? {
+                ParseResult::Incomplete => return Ok(()),
+                ParseResult::Done { consumed, source } => {
+                    *buffer = pending.split_off(consumed);
+                    state.proxy_header = None;
+                    state.proxied_peer = source;
+                }
+            }
+            state.handshaking()
+        };
+
+        if !handshaking {
+            if let Some(handler) = self.inner.registry.handlers().connect {
+                handler(self);
+            }
+        }
+        Ok(())
+    }
+
+    // 尽力发送 close_notify，输出缓冲区还有数据时跳过，避免打乱记录顺序
+    #[cfg(feature = "tls")]
+    fn send_close_notify(&self, state: &mut ConnectionState) {
+        if !state.output.is_empty() || state.write_shutdown {
+            return;
+        }
+        if let Some(tls) = state.tls.as_mut() {
+            let _ = send_vectored_raw(self.inner.fd, &[IoSlice::new(&tls::close_notify(tls))]);
+        }
+    }
+
+    // 解密收到的数据并发送握手响应，buffer 被替换为明文。非 TLS 连接返回 None
+    #[cfg(feature = "tls")]
+    fn handle_tls_read(&self, buffer: &mut Vec<u8>) -> io::Result<Option<bool>> {
+        let mut plaintext = Vec::new();
+        let (peer_closed, handshake_done) = {
+            let mut state = self.inner.state.lock().unwrap();
+            let tls = match state.tls.as_mut() {
+                Some(tls) => tls,
+                None => return Ok(None),
+            };
+
+            let was_handshaking = tls.is_handshaking();
+            let result = tls::read_tls(tls, buffer, &mut plaintext);
+            let handshake_done = was_handshaking && !tls.is_handshaking();
+            // 握手消息和告警需要立即发出，出错时也要先把告警发给对端
+            let output = tls::take_output(tls);
+            let written = self.queue_output(&mut state, &[IoSlice::new(&output)], None);
+            (result?, written.map(|_| handshake_done)?)
+        };
+
+        *buffer = plaintext;
+        if handshake_done {
+            if let Some(handler) = self.inner.registry.handlers().connect {
+                handler(self);
+            }
+        }
+        Ok(Some(peer_closed))
+    }
+
+    fn check_timeouts(&self) {
+        let options = &self.inner.options;
+        let now = Instant::now();
+        let reason = {
+            let state = self.inner.state.lock().unwrap();
+            if state.closed {
+                return;
+            }
+
+            let expired = |since: Instant, timeout: Option<Duration>| {
+                timeout.is_some_and(|timeout| now.duration_since(since) >= timeout)
+            };
+            if !state.received_any && expired(state.created, options.first_byte_timeout) {
+                CloseReason::FirstByteTimeout
+            } else if !state.output.is_empty() && expired(state.last_write_progress, options.write_stall_timeout) {
+                CloseReason::WriteStallTimeout
+            } else if expired(state.last_activity, options.idle_timeout) {
+                CloseReason::IdleTimeout
+            } else {
+                return;
+            }
+        };
+        self.close_with(reason);
+    }
+
+    fn handle_events(&self, events: u32, buffer: &mut Vec<u8>) {
+        if self.is_closed() {
+            return;
+        }
+
+        let mut reason = None;
+        let mut limited = false;
+        if events & (libc::EPOLLIN as u32) != 0 {
+            // 对端半关闭时一直读到 EOF，LT 模式下只读一次会丢掉缓冲区中剩余的数据
+            let until_eof = events & (libc::EPOLLRDHUP as u32) != 0;
+            match self.handle_read(buffer, until_eof) {
+                Ok(ReadResult::Eof) => reason = Some(CloseReason::PeerEof),
+                Ok(ReadResult::Limited) => limited = true,
+                Ok(ReadResult::Done) => {}
+                Err(e) => {
+                    eprintln!("Error handling read: {}", e);
+                    reason = Some(CloseReason::Error(e.kind()));
+                }
+            }
+        }
+        // ET 模式下剩余数据不会再触发事件，需要重新注册
+        if limited && self.inner.options.edge_triggered {
+            self.rearm_read();
+        }
+
+        // 还有数据没读完时不关闭，等读到 EOF
+        if reason.is_none() && !limited {
+            if events & ((libc::EPOLLHUP | libc::EPOLLERR) as u32) != 0 {
+                reason = Some(match socket::socket_error(self.inner.fd) {
+                    Some(e) => CloseReason::Error(e.kind()),
+                    None if events & (libc::EPOLLERR as u32) != 0 => CloseReason::Error(io::ErrorKind::Other),
+                    None => CloseReason::PeerEof,
+                });
+            } else if events & (libc::EPOLLRDHUP as u32) != 0 {
+                reason = Some(CloseReason::PeerRdhup);
+            }
+        }
+
+        if let Some(reason) = reason {
+            // EPOLLHUP 表示两个方向都已关闭，只有对端半关闭时才能继续写
+            let half_closed = matches!(reason, CloseReason::PeerEof | CloseReason::PeerRdhup)
+                && events & (libc::EPOLLHUP as u32) == 0;
+            if !half_closed || !self.close_after_peer_shutdown(reason) {
+                self.close_with(reason);
+                return;
+            }
+        }
+        if events & (libc::EPOLLOUT as u32) != 0 {
+            self.handle_write();
+        }
+    }
+
+    // 输出缓冲区非空时停止读并在写完后关闭，返回 false 表示可以立即关闭
+    fn close_after_peer_shutdown(&self, reason: CloseReason) -> bool {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.output.is_empty() {
+            return false;
+        }
+        state.close_after_flush = true;
+        state.peer_shutdown = Some(reason);
+        // LT 模式下 EPOLLRDHUP 会一直触发，和 EPOLLIN 一起去掉
+        let interest = state.interest & !((libc::EPOLLIN | libc::EPOLLRDHUP) as u32);
+        self.set_interest(&mut state, interest);
+        true
+    }
+
+    fn handle_read(&self, buffer: &mut Vec<u8>, until_eof: bool) -> io::Result<ReadResult> {
+        let options = &self.inner.options;
+        let limit = options.read_buffer_size.saturating_mul(MAX_READS_PER_EVENT);
+        let mut eof = false;
+        let mut limited = false;
+        let mut error = None;
+
+        // ET 模式下读到 EAGAIN 或达到上限为止，达到上限时由调用方重新注册
+        loop {
+            match read_some(self.inner.fd, buffer, options.read_buffer_size) {
+                Ok(0) => {
+                    eof = true;
+                    break;
+                }
+                Ok(_) if !options.edge_triggered && !until_eof => break,
+                Ok(_) if buffer.len() + options.read_buffer_size > limit => {
+                    limited = true;
+                    break;
+                }
+                Ok(_) => continue,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        if !buffer.is_empty() {
+            {
+                let mut state = self.inner.state.lock().unwrap();
+                state.received_any = true;
+                state.last_activity = Instant::now();
+            }
+            if let Err(e) = self.handle_proxy_header(buffer) {
+                buffer.clear();
+                error = Some(e);
+            }
+            #[cfg(feature = "tls")]
+            if error.is_none() && !buffer.is_empty() {
+                match self.handle_tls_read(buffer) {
+                    Ok(peer_closed) => eof |= peer_closed.unwrap_or(false),
+                    Err(e) => {
+                        buffer.clear();
+                        error = Some(e);
+                    }
+                }
+            }
+            if !buffer.is_empty() {
+                if let Err(e) = self.deliver(buffer) {
+                    error = Some(e);
+                }
+            }
+            buffer.clear();
+            // 一次读了多块时缓冲区会变大，缩回去避免空闲连接长期占用
+            buffer.shrink_to(options.read_buffer_size);
+        }
+
+        match error {
+            Some(e) => Err(e),
+            None if eof => Ok(ReadResult::Eof),
+            None if limited => Ok(ReadResult::Limited),
+            None => Ok(ReadResult::Done),
+        }
+    }
+
+    // 设置了 codec 时按帧调用 message handler，不完整的帧留在输入缓冲区
+    fn deliver(&self, data: &[u8]) -> io::Result<()> {
+        let handler = match self.inner.registry.handlers().message {
+            Some(handler) => handler,
+            None => return Ok(()),
+        };
+        // 只在事件循环线程上访问，取出后调用回调时不持有锁
+        let mut framing = match self.inner.framing.lock().unwrap().take() {
+            Some(framing) => framing,
+            None => {
+                handler(self, data);
+                return Ok(());
+            }
+        };
+
+        framing.input.extend_from_slice(data);
+        let mut offset = 0;
+        let result = loop {
+            if self.is_closed() {
+                break Ok(());
+            }
+            match framing.decoder.decode(&framing.input[offset..]) {
+                Ok(Some((0, _))) => {
+                    break Err(io::Error::new(io::ErrorKind::InvalidData, "decoder consumed no bytes"));
+                }
+                Ok(Some((consumed, range))) => {
+                    handler(self, &framing.input[offset + range.start..offset + range.end]);
+                    offset += consumed;
+                }
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        framing.input.drain(..offset);
+        *self.inner.framing.lock().unwrap() = Some(framing);
+        result
+    }
+
+    fn handle_write(&self) {
+        let (drained_below_low, close) = {
+            let mut state = self.inner.state.lock().unwrap();
+            if state.closed {
+                return;
+            }
+
+            match state.output.write_to(self.inner.fd) {
+                Ok(0) => {}
+                Ok(_) => {
+                    let now = Instant::now();
+                    state.last_activity = now;
+                    state.last_write_progress = now;
+                }
+                Err(e) => {
+                    eprintln!("Error writing to fd {}: {}", self.inner.fd, e);
+                    drop(state);
+                    self.close_with(CloseReason::Error(e.kind()));
+                    return;
+                }
+            }
+
+            let drained_below_low = state.above_high_watermark
+                && state.output.len() <= self.inner.options.low_watermark;
+            if drained_below_low {
+                state.above_high_watermark = false;
+            }
+
+            if state.output.is_empty() {
+                let interest = state.interest & !(libc::EPOLLOUT as u32);
+                self.set_interest(&mut state, interest);
+                if state.draining {
+                    self.shutdown_write(&mut state);
+                }
+            }
+            let close = state.output.is_empty() && state.close_after_flush;
+            (drained_below_low, close.then(|| state.peer_shutdown.unwrap_or(CloseReason::Local)))
+        };
+
+        if drained_below_low {
+            if let Some(handler) = self.inner.registry.handlers().low_watermark {
+                handler(self);
+            }
+        }
+        if let Some(reason) = close {
+            self.close_with(reason);
+        }
+    }
+}
@@ -1 +1,11 @@
-pub mod tcp_server;
\ No newline at end of file
+pub mod codec;
+pub mod connection;
+pub(crate) mod proxy_protocol;
+pub mod server;
+pub(crate) mod socket;
+pub mod tcp_client;
+pub mod tcp_server;
+#[cfg(feature = "tls")]
+pub mod tls;
+pub mod udp_server;
+pub mod unix_server;
@@ -0,0 +1,104 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+// v1 头部包括结尾的 CRLF 最长 107 字节
+const V1_MAX_LEN: usize = 107;
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+const V2_HEADER_LEN: usize = 16;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ParseResult {
+    Incomplete,
+    // consumed 为头部长度，source 为 None 表示 LOCAL/UNKNOWN，沿用套接字地址
+    Done { consumed: usize, source: Option<SocketAddr> },
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid PROXY protocol header: {}", message))
+}
+
+fn is_prefix(buf: &[u8], expected: &[u8]) -> bool {
+    let len = buf.len().min(expected.len());
+    buf[..len] == expected[..len]
+}
+
+pub(crate) fn parse(buf: &[u8]) -> io::Result<ParseResult> {
+    if is_prefix(buf, V2_SIGNATURE) {
+        if buf.len() < V2_HEADER_LEN {
+            return Ok(ParseResult::Incomplete);
+        }
+        return parse_v2(buf);
+    }
+    if is_prefix(buf, V1_PREFIX) {
+        return parse_v1(buf);
+    }
+    Err(invalid("missing signature"))
+}
+
+fn parse_v1(buf: &[u8]) -> io::Result<ParseResult> {
+    let end = match buf.windows(2).position(|window| window == b"\r\n") {
+        Some(end) if end + 2 <= V1_MAX_LEN => end,
+        Some(_) => return Err(invalid("v1 header too long")),
+        None if buf.len() < V1_MAX_LEN => return Ok(ParseResult::Incomplete),
+        None => return Err(invalid("v1 header too long")),
+    };
+
+    let line = std::str::from_utf8(&buf[V1_PREFIX.len()..end]).map_err(|_| invalid("v1 header is not ASCII"))?;
+    let fields: Vec<&str> = line.split(' ').collect();
+    let consumed = end + 2;
+    let source = match fields.as_slice() {
+        ["UNKNOWN", ..] => None,
+        [protocol @ ("TCP4" | "TCP6"), src, dst, src_port, dst_port] => {
+            let src: IpAddr = src.parse().map_err(|_| invalid("bad source address"))?;
+            let dst: IpAddr = dst.parse().map_err(|_| invalid("bad destination address"))?;
+            let src_port: u16 = src_port.parse().map_err(|_| invalid("bad source port"))?;
+            dst_port.parse::<u16>().map_err(|_| invalid("bad destination port"))?;
+            let v4 = *protocol == "TCP4";
+            if src.is_ipv4() != v4 || dst.is_ipv4() != v4 {
+                return Err(invalid("address family mismatch"));
+            }
+            Some(SocketAddr::new(src, src_port))
+        }
+        _ => return Err(invalid("malformed v1 header")),
+    };
+    Ok(ParseResult::Done { consumed, source })
+}
+
+fn parse_v2(buf: &[u8]) -> io::Result<ParseResult> {
+    let version = buf[12] >> 4;
+    let command = buf[12] & 0x0f;
+    if version != 2 {
+        return Err(invalid("unsupported version"));
+    }
+
+    let len = u16::from_be_bytes([buf[14], buf[15]]) as usize;
+    let consumed = V2_HEADER_LEN + len;
+    if buf.len() < consumed {
+        return Ok(ParseResult::Incomplete);
+    }
+    let addresses = &buf[V2_HEADER_LEN..consumed];
+
+    let source = match command {
+        // LOCAL：代理自身的健康检查等连接
+        0x0 => None,
+        0x1 => match buf[13] >> 4 {
+            0x1 if addresses.len() >= 12 => {
+                let ip = Ipv4Addr::new(addresses[0], addresses[1], addresses[2], addresses[3]);
+                let port = u16::from_be_bytes([addresses[8], addresses[9]]);
+                Some(SocketAddr::new(IpAddr::V4(ip), port))
+            }
+            0x2 if addresses.len() >= 36 => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(&addresses[..16]);
+                let port = u16::from_be_bytes([addresses[32], addresses[33]]);
+                Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port))
+            }
+            0x1 | 0x2 => return Err(invalid("address block too short")),
+            // AF_UNSPEC 和 AF_UNIX 没有可用的 IP 地址
+            _ => None,
+        },
+        _ => return Err(invalid("unsupported command")),
+    };
+    Ok(ParseResult::Done { consumed, source })
+}
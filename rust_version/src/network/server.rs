@@ -0,0 +1,500 @@
+use crate::core::reactor::{Reactor, ReactorHandle};
+use crate::core::reactor_pool::ReactorPool;
+use crate::network::codec::Codec;
+use crate::network::connection::{CloseReason, Connection, ConnectionRegistry};
+use crate::network::socket;
+use crate::utils::Logger;
+use std::fs::File;
+use std::io::{self, IoSlice};
+use std::os::unix::io::RawFd;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+type ReceiveHandler = Box<dyn FnMut(RawFd, &[u8], usize) + Send>;
+pub(crate) type ListenerBinder = Box<dyn Fn() -> io::Result<RawFd> + Send>;
+
+struct ServerState {
+    main_loop: ReactorHandle,
+    pool: Option<Arc<ReactorPool>>,
+    connections: Arc<ConnectionRegistry>,
+    // fd 耗尽时释放这个预留的 fd 来接受并立即关闭新连接，避免监听套接字一直可读导致空转
+    reserved_fd: RawFd,
+}
+
+fn open_reserved_fd() -> RawFd {
+    unsafe { libc::open(c"/dev/null".as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC) }
+}
+
+impl Drop for ServerState {
+    fn drop(&mut self) {
+        if self.reserved_fd >= 0 {
+            unsafe {
+                libc::close(self.reserved_fd);
+            }
+        }
+    }
+}
+
+impl ServerState {
+    fn next_loop(&self) -> ReactorHandle {
+        self.pool
+            .as_ref()
+            .and_then(|pool| pool.next_handle())
+            .unwrap_or_else(|| self.main_loop.clone())
+    }
+}
+
+// 监听套接字的创建方式由 L 决定（TCP / Unix），accept 之后的连接处理完全共用
+pub struct Server<L> {
+    state: Arc<Mutex<ServerState>>,
+    reactor: Option<Reactor>,
+    listener: L,
+    server_fd: RawFd,
+    reuse_port_binder: Option<ListenerBinder>,
+    // 每个监听套接字及其所属的事件循环，None 表示主 reactor
+    listeners: Vec<(RawFd, Option<ReactorHandle>)>,
+    thread_num: usize,
+    running: Arc<AtomicBool>,
+    accepting: bool,
+}
+
+impl<L> Server<L> {
+    pub(crate) fn from_listener(reactor: Reactor, server_fd: RawFd, listener: L) -> Self {
+        Server {
+            state: Arc::new(Mutex::new(ServerState {
+                main_loop: reactor.handle(),
+                pool: None,
+                connections: Arc::new(ConnectionRegistry::new()),
+                reserved_fd: open_reserved_fd(),
+            })),
+            reactor: Some(reactor),
+            listener,
+            server_fd,
+            reuse_port_binder: None,
+            listeners: Vec::new(),
+            thread_num: 0,
+            running: Arc::new(AtomicBool::new(false)),
+            accepting: false,
+        }
+    }
+
+    pub(crate) fn listener(&self) -> &L {
+        &self.listener
+    }
+
+    pub(crate) fn listen_fd(&self) -> RawFd {
+        self.server_fd
+    }
+
+    pub(crate) fn set_reuse_port_binder(&mut self, binder: ListenerBinder) {
+        self.reuse_port_binder = Some(binder);
+    }
+
+    pub fn set_thread_num(&mut self, thread_num: usize) {
+        self.thread_num = thread_num;
+    }
+
+    pub fn set_edge_triggered(&mut self, edge_triggered: bool) {
+        self.registry().options.lock().unwrap().edge_triggered = edge_triggered;
+    }
+
+    pub fn set_read_buffer_size(&mut self, size: usize) {
+        self.registry().options.lock().unwrap().read_buffer_size = size.max(1);
+    }
+
+    pub fn set_max_connections(&mut self, max_connections: usize) {
+        self.registry().options.lock().unwrap().max_connections = max_connections;
+    }
+
+    pub fn set_max_connections_per_ip(&mut self, max_connections: usize) {
+        self.registry().options.lock().unwrap().max_connections_per_ip = max_connections;
+    }
+
+    pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.registry().options.lock().unwrap().idle_timeout = timeout;
+    }
+
+    pub fn set_first_byte_timeout(&mut self, timeout: Option<Duration>) {
+        self.registry().options.lock().unwrap().first_byte_timeout = timeout;
+    }
+
+    pub fn set_write_stall_timeout(&mut self, timeout: Option<Duration>) {
+        self.registry().options.lock().unwrap().write_stall_timeout = timeout;
+    }
+
+    // 之后 accept 的连接都使用 TLS，证书可用 tls::server_config 按 SNI 配置
+    #[cfg(feature = "tls")]
+    pub fn set_tls_config(&mut self, config: Arc<rustls::ServerConfig>) {
+        *self.registry().tls.lock().unwrap() = Some(config);
+    }
+
+    // 接收 HAProxy 等代理发送的 PROXY protocol v1/v2 头部，用其中的客户端地址作为 peer_addr
+    pub fn set_proxy_protocol(&mut self, enabled: bool) {
+        self.registry().options.lock().unwrap().proxy_protocol = enabled;
+    }
+
+    // 每个连接使用 codec 的独立副本，message handler 每次收到一个完整的帧
+    pub fn set_codec<C>(&mut self, codec: C)
+    where
+        C: Codec + Clone + Sync + 'static,
+    {
+        let factory = move || Box::new(codec.clone()) as Box<dyn Codec>;
+        *self.registry().codec.lock().unwrap() = Some(Arc::new(factory));
+    }
+
+    pub fn set_write_watermarks(&mut self, low: usize, high: usize) {
+        let registry = self.registry();
+        let mut options = registry.options.lock().unwrap();
+        options.low_watermark = low.min(high);
+        options.high_watermark = high;
+    }
+
+    fn registry(&self) -> Arc<ConnectionRegistry> {
+        Arc::clone(&self.state.lock().unwrap().connections)
+    }
+
+    // event_loop 为 None 时按轮询分配给 IO 线程，否则连接留在接受它的线程上
+    fn accept(
+        server_fd: RawFd,
+        event_loop: Option<&ReactorHandle>,
+        state: &Arc<Mutex<ServerState>>,
+    ) -> io::Result<()> {
+        let (client_fd, peer_addr) = unsafe {
+            let mut client_addr: libc::sockaddr_storage = std::mem::zeroed();
+            let mut client_len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+
+            let client_fd = libc::accept(
+                server_fd,
+                &mut client_addr as *mut _ as *mut libc::sockaddr,
+                &mut client_len,
+            );
+
+            if client_fd == -1 {
+                let err = io::Error::last_os_error();
+                match err.raw_os_error() {
+                    Some(libc::EMFILE) | Some(libc::ENFILE) => Self::reject_with_reserved_fd(server_fd, state),
+                    _ if err.kind() != io::ErrorKind::WouldBlock => {
+                        eprintln!("Failed to accept connection: {}", err);
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+
+            let flags = libc::fcntl(client_fd, libc::F_GETFL, 0);
+            if flags == -1 || libc::fcntl(client_fd, libc::F_SETFL, flags | libc::O_NONBLOCK) == -1 {
+                let err = io::Error::last_os_error();
+                libc::close(client_fd);
+                return Err(err);
+            }
+            (client_fd, socket::sockaddr_to_addr(&client_addr, client_len))
+        };
+
+        let local_addr = socket::local_addr(client_fd).unwrap_or(None);
+        match peer_addr {
+            Some(addr) => Logger::instance().info(&format!("Accepted connection {} from {}", client_fd, addr)),
+            None => Logger::instance().info(&format!("Accepted connection {}", client_fd)),
+        }
+
+        let (event_loop, connections) = {
+            let state = state.lock().unwrap();
+            let event_loop = event_loop.cloned().unwrap_or_else(|| state.next_loop());
+            (event_loop, Arc::clone(&state.connections))
+        };
+        if let Err(e) = connections.register(client_fd, peer_addr, local_addr, event_loop) {
+            Logger::instance().warning(&format!("Rejected connection {}: {}", client_fd, e));
+            unsafe {
+                libc::close(client_fd);
+            }
+        }
+        Ok(())
+    }
+
+    fn reject_with_reserved_fd(server_fd: RawFd, state: &Arc<Mutex<ServerState>>) {
+        let mut state = state.lock().unwrap();
+        if state.reserved_fd < 0 {
+            eprintln!("Failed to accept connection: too many open files");
+            return;
+        }
+
+        unsafe {
+            libc::close(state.reserved_fd);
+            let client_fd = libc::accept(server_fd, std::ptr::null_mut(), std::ptr::null_mut());
+            if client_fd >= 0 {
+                libc::close(client_fd);
+            }
+        }
+        state.reserved_fd = open_reserved_fd();
+        Logger::instance().warning("Rejected connection: too many open files");
+    }
+
+    pub fn accept_connection(&mut self) -> io::Result<()> {
+        Self::accept(self.server_fd, None, &self.state)
+    }
+
+    fn accept_handler(
+        server_fd: RawFd,
+        event_loop: Option<ReactorHandle>,
+        state: &Arc<Mutex<ServerState>>,
+    ) -> impl FnMut(u32) + Send + 'static {
+        let state = Arc::clone(state);
+        move |events: u32| {
+            if events & (libc::EPOLLIN as u32) != 0 {
+                if let Err(e) = Self::accept(server_fd, event_loop.as_ref(), &state) {
+                    eprintln!("Accept error happened: {}", e);
+                }
+            }
+        }
+    }
+
+    pub fn start(&mut self) -> io::Result<()> {
+        let mut pool_handles = Vec::new();
+        if self.thread_num > 0 {
+            let pool = ReactorPool::new(self.thread_num)?;
+            pool_handles = pool.handles().to_vec();
+            self.state.lock().unwrap().pool = Some(Arc::new(pool));
+        }
+
+        self.running.store(true, Ordering::SeqCst);
+        self.accepting = true;
+        let main_loop = self.state.lock().unwrap().main_loop.clone();
+
+        // SO_REUSEPORT 模式：第一个 IO 线程复用已绑定的套接字，其余线程各自再绑定一个
+        if let (Some(binder), false) = (self.reuse_port_binder.as_ref(), pool_handles.is_empty()) {
+            for (i, handle) in pool_handles.into_iter().enumerate() {
+                let listen_fd = if i == 0 { self.server_fd } else { binder()? };
+                self.listeners.push((listen_fd, Some(handle.clone())));
+
+                let accept_handler = Self::accept_handler(listen_fd, Some(handle.clone()), &self.state);
+                handle.post(move |reactor| {
+                    if let Err(e) = reactor.add_handler(listen_fd, libc::EPOLLIN as u32, accept_handler) {
+                        eprintln!("Failed to register listener: {}", e);
+                    }
+                });
+            }
+            return Ok(());
+        }
+
+        let server_fd = self.server_fd;
+        self.listeners.push((server_fd, None));
+        let accept_handler = Self::accept_handler(server_fd, None, &self.state);
+        match self.reactor.as_mut() {
+            Some(reactor) => reactor.add_handler(server_fd, libc::EPOLLIN as u32, accept_handler)?,
+            None => {
+                main_loop.post(move |reactor| {
+                    if let Err(e) = reactor.add_handler(server_fd, libc::EPOLLIN as u32, accept_handler) {
+                        eprintln!("Failed to register listener: {}", e);
+                    }
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // 在监听套接字所属的事件循环上移除并关闭它
+    fn stop_accepting(&mut self) {
+        if !self.accepting {
+            return;
+        }
+        self.accepting = false;
+
+        let main_loop = self.state.lock().unwrap().main_loop.clone();
+        for (listen_fd, event_loop) in std::mem::take(&mut self.listeners) {
+            match (event_loop, self.reactor.as_mut()) {
+                (None, Some(reactor)) => Self::close_listener(reactor, listen_fd),
+                (event_loop, _) => event_loop
+                    .unwrap_or_else(|| main_loop.clone())
+                    .post(move |reactor| Self::close_listener(reactor, listen_fd)),
+            }
+        }
+        // 主监听套接字已经交给事件循环关闭，Drop 中不再关闭
+        self.server_fd = -1;
+    }
+
+    fn close_listener(reactor: &mut Reactor, listen_fd: RawFd) {
+        if let Err(e) = reactor.remove_handler(listen_fd) {
+            eprintln!("Error removing listener {}: {}", listen_fd, e);
+        }
+        unsafe {
+            libc::close(listen_fd);
+        }
+    }
+
+    // 优雅关闭：停止 accept，通知连接并等待排空，超过 grace_period 后强制关闭剩余连接。
+    // 会阻塞调用线程，不能在事件循环线程上调用
+    pub fn shutdown(&mut self, grace_period: Duration) -> io::Result<()> {
+        self.stop_accepting();
+
+        let connections = self.registry();
+        connections.drain_all();
+        if !connections.wait_drained(grace_period) {
+            eprintln!("Grace period expired, force closing {} connections", connections.len());
+        }
+        self.stop()
+    }
+
+    pub fn stop(&mut self) -> io::Result<()> {
+        println!("Server stopping...");
+        self.running.store(false, Ordering::SeqCst);
+
+        let (main_loop, pool, connections) = {
+            let mut state = self.state.lock().unwrap();
+            (state.main_loop.clone(), state.pool.take(), Arc::clone(&state.connections))
+        };
+
+        // 先停止 accept 再关闭连接，关闭任务先于 stop 投递，事件循环退出前会执行完
+        self.stop_accepting();
+        connections.close_all(CloseReason::ServerShutdown);
+
+        main_loop.stop();
+
+        if let Some(pool) = pool {
+            pool.stop();
+        }
+
+        println!("Server stopped");
+        Ok(())
+    }
+
+    pub fn set_connect_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&Connection) + Send + Sync + 'static,
+    {
+        self.registry().handlers.lock().unwrap().connect = Some(Arc::new(handler));
+    }
+
+    pub fn set_message_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&Connection, &[u8]) + Send + Sync + 'static,
+    {
+        self.registry().handlers.lock().unwrap().message = Some(Arc::new(handler));
+    }
+
+    pub fn set_close_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&Connection, CloseReason) + Send + Sync + 'static,
+    {
+        self.registry().handlers.lock().unwrap().close = Some(Arc::new(handler));
+    }
+
+    pub fn set_shutdown_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&Connection) + Send + Sync + 'static,
+    {
+        self.registry().handlers.lock().unwrap().shutdown = Some(Arc::new(handler));
+    }
+
+    // 旧接口，基于 fd 的回调包装成 message handler
+    pub fn set_receive_handler<F>(&mut self, handler: F)
+    where
+        F: FnMut(RawFd, &[u8], usize) + Send + 'static,
+    {
+        let handler: Mutex<ReceiveHandler> = Mutex::new(Box::new(handler));
+        self.set_message_handler(move |conn, data| {
+            (handler.lock().unwrap())(conn.fd(), data, data.len());
+        });
+    }
+
+    pub fn set_high_watermark_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&Connection, usize) + Send + Sync + 'static,
+    {
+        self.registry().handlers.lock().unwrap().high_watermark = Some(Arc::new(handler));
+    }
+
+    pub fn set_low_watermark_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&Connection) + Send + Sync + 'static,
+    {
+        self.registry().handlers.lock().unwrap().low_watermark = Some(Arc::new(handler));
+    }
+
+    pub fn connection(&self, client_fd: RawFd) -> Option<Connection> {
+        self.registry().get(client_fd)
+    }
+
+    pub fn peer_addr(&self, client_fd: RawFd) -> Option<SocketAddr> {
+        self.connection(client_fd).and_then(|conn| conn.peer_addr())
+    }
+
+    pub fn connection_count(&self) -> usize {
+        self.registry().len()
+    }
+
+    pub fn connections_from(&self, ip: IpAddr) -> usize {
+        self.registry().connections_from(ip)
+    }
+
+    fn connected(&self, client_fd: RawFd) -> io::Result<Connection> {
+        self.connection(client_fd)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "connection not found"))
+    }
+
+    pub fn send(&self, client_fd: RawFd, data: &[u8]) -> io::Result<usize> {
+        self.connected(client_fd)?.send(data)?;
+        Ok(data.len())
+    }
+
+    pub fn send_vectored(&self, client_fd: RawFd, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.connected(client_fd)?.send_vectored(bufs)?;
+        Ok(bufs.iter().map(|buf| buf.len()).sum())
+    }
+
+    pub fn pending_bytes(&self, client_fd: RawFd) -> io::Result<usize> {
+        Ok(self.connected(client_fd)?.pending_bytes())
+    }
+
+    pub fn send_file(&self, client_fd: RawFd, file: File, offset: u64, len: u64) -> io::Result<()> {
+        self.connected(client_fd)?.send_file(file, offset, len)
+    }
+
+    pub fn pause_read(&self, client_fd: RawFd) -> io::Result<()> {
+        self.connected(client_fd)?.pause_read()
+    }
+
+    pub fn resume_read(&self, client_fd: RawFd) -> io::Result<()> {
+        self.connected(client_fd)?.resume_read()
+    }
+
+    pub fn close_after_flush(&self, client_fd: RawFd) -> io::Result<()> {
+        self.connected(client_fd)?.close_after_flush();
+        Ok(())
+    }
+
+    pub fn join_group(&self, client_fd: RawFd, group: &str) -> io::Result<()> {
+        self.connected(client_fd)?.join_group(group)
+    }
+
+    pub fn leave_group(&self, client_fd: RawFd, group: &str) -> io::Result<bool> {
+        Ok(self.connected(client_fd)?.leave_group(group))
+    }
+
+    pub fn broadcast(&self, group: &str, data: &[u8]) -> usize {
+        self.registry().broadcast(group, data, None)
+    }
+
+    pub fn group_size(&self, group: &str) -> usize {
+        self.registry().group_size(group)
+    }
+
+    // 主 reactor 只能取出一次，之后返回 None
+    pub fn get_reactor(&mut self) -> Option<Reactor> {
+        self.reactor.take()
+    }
+}
+
+impl<L> Drop for Server<L> {
+    fn drop(&mut self) {
+        if self.running.load(Ordering::SeqCst) {
+            let _ = self.stop();
+        }
+        if self.server_fd >= 0 {
+            unsafe {
+                let _ = libc::close(self.server_fd);
+            }
+        }
+    }
+}
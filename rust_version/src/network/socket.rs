@@ -0,0 +1,172 @@
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs};
+use std::os::unix::io::RawFd;
+
+// 按 ss_family 解析 accept/getsockname 返回的地址，IPv6 不会被截断
+pub(crate) fn sockaddr_to_addr(storage: &libc::sockaddr_storage, len: libc::socklen_t) -> Option<SocketAddr> {
+    match storage.ss_family as libc::c_int {
+        libc::AF_INET if len as usize >= std::mem::size_of::<libc::sockaddr_in>() => {
+            let addr = unsafe { &*(storage as *const _ as *const libc::sockaddr_in) };
+            let ip = Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr));
+            Some(SocketAddr::V4(SocketAddrV4::new(ip, u16::from_be(addr.sin_port))))
+        }
+        libc::AF_INET6 if len as usize >= std::mem::size_of::<libc::sockaddr_in6>() => {
+            let addr = unsafe { &*(storage as *const _ as *const libc::sockaddr_in6) };
+            let ip = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+            // 双栈监听时 IPv4 客户端以 ::ffff:a.b.c.d 的形式出现，还原成 IPv4 地址
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return Some(SocketAddr::V4(SocketAddrV4::new(v4, u16::from_be(addr.sin6_port))));
+            }
+            Some(SocketAddr::V6(SocketAddrV6::new(
+                ip,
+                u16::from_be(addr.sin6_port),
+                addr.sin6_flowinfo,
+                addr.sin6_scope_id,
+            )))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn local_addr(fd: RawFd) -> io::Result<Option<SocketAddr>> {
+    unsafe {
+        let mut storage: libc::sockaddr_storage = std::mem::zeroed();
+        let mut len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        if libc::getsockname(fd, &mut storage as *mut _ as *mut libc::sockaddr, &mut len) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(sockaddr_to_addr(&storage, len))
+    }
+}
+
+pub(crate) fn addr_to_sockaddr(addr: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(v4) => {
+            let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_port = v4.port().to_be();
+            sin.sin_addr.s_addr = u32::from(*v4.ip()).to_be();
+            std::mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(v6) => {
+            let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+            sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sin6.sin6_port = v6.port().to_be();
+            sin6.sin6_addr.s6_addr = v6.ip().octets();
+            sin6.sin6_flowinfo = v6.flowinfo();
+            sin6.sin6_scope_id = v6.scope_id();
+            std::mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+    (storage, len as libc::socklen_t)
+}
+
+pub(crate) fn set_int_option(fd: RawFd, level: libc::c_int, name: libc::c_int, value: libc::c_int) -> io::Result<()> {
+    let result = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            name,
+            &value as *const _ as *const libc::c_void,
+            std::mem::size_of_val(&value) as libc::socklen_t,
+        )
+    };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// 套接字选项必须在 bind 之前设置，所以不能再用 std::net::TcpListener
+pub(crate) fn bind_tcp_listener(addr: &SocketAddr, v6_only: Option<bool>, reuse_port: bool, backlog: i32) -> io::Result<RawFd> {
+    let family = match addr {
+        SocketAddr::V4(_) => libc::AF_INET,
+        SocketAddr::V6(_) => libc::AF_INET6,
+    };
+    let fd = unsafe { libc::socket(family, libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC, 0) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let result = (|| {
+        set_int_option(fd, libc::SOL_SOCKET, libc::SO_REUSEADDR, 1)?;
+        if reuse_port {
+            set_int_option(fd, libc::SOL_SOCKET, libc::SO_REUSEPORT, 1)?;
+        }
+        if let (SocketAddr::V6(_), Some(v6_only)) = (addr, v6_only) {
+            set_int_option(fd, libc::IPPROTO_IPV6, libc::IPV6_V6ONLY, v6_only as libc::c_int)?;
+        }
+
+        let (storage, len) = addr_to_sockaddr(addr);
+        unsafe {
+            if libc::bind(fd, &storage as *const _ as *const libc::sockaddr, len) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::listen(fd, backlog) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => Ok(fd),
+        Err(e) => {
+            unsafe { libc::close(fd) };
+            Err(e)
+        }
+    }
+}
+
+// 去掉 "[::1]" 形式的方括号，("::1", port) 和主机名都交给 to_socket_addrs 解析
+pub(crate) fn resolve(ip: &str, port: u16) -> io::Result<SocketAddr> {
+    let host = ip.trim_start_matches('[').trim_end_matches(']');
+    (host, port).to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("could not resolve address {}", ip))
+    })
+}
+
+pub(crate) fn socket_error(fd: RawFd) -> Option<io::Error> {
+    let mut error: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_ERROR,
+            &mut error as *mut _ as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if result == -1 {
+        return Some(io::Error::last_os_error());
+    }
+    (error != 0).then(|| io::Error::from_raw_os_error(error))
+}
+
+// 返回的 bool 表示连接是否仍在进行中（EINPROGRESS），需要等待 EPOLLOUT
+pub(crate) fn connect_nonblocking(addr: &SocketAddr) -> io::Result<(RawFd, bool)> {
+    let family = match addr {
+        SocketAddr::V4(_) => libc::AF_INET,
+        SocketAddr::V6(_) => libc::AF_INET6,
+    };
+    let fd = unsafe { libc::socket(family, libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC, 0) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let (storage, len) = addr_to_sockaddr(addr);
+    if unsafe { libc::connect(fd, &storage as *const _ as *const libc::sockaddr, len) } == 0 {
+        return Ok((fd, false));
+    }
+
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        Some(libc::EINPROGRESS) | Some(libc::EINTR) => Ok((fd, true)),
+        _ => {
+            unsafe { libc::close(fd) };
+            Err(err)
+        }
+    }
+}
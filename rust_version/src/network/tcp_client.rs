@@ -0,0 +1,348 @@
+use crate::core::reactor::{Reactor, ReactorHandle, TimerId};
+use crate::network::codec::Codec;
+use crate::network::connection::{
+    CloseHandler, CloseReason, ConnectHandler, Connection, ConnectionRegistry,
+};
+use crate::network::socket;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::net::SocketAddr;
+use std::os::unix::io::RawFd;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+type ConnectErrorHandler = Arc<dyn Fn(&io::Error) + Send + Sync>;
+
+struct ClientState {
+    connection: Option<Connection>,
+    connecting_fd: Option<RawFd>,
+    retry_timer: Option<TimerId>,
+    attempt: u32,
+    active: bool,
+    reconnect: bool,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    connect_handler: Option<ConnectHandler>,
+    close_handler: Option<CloseHandler>,
+    error_handler: Option<ConnectErrorHandler>,
+}
+
+struct ClientInner {
+    addr: SocketAddr,
+    event_loop: ReactorHandle,
+    registry: Arc<ConnectionRegistry>,
+    state: Mutex<ClientState>,
+}
+
+pub struct TcpClient {
+    inner: Arc<ClientInner>,
+}
+
+// 指数退避加抖动：取 [delay/2, delay] 之间的随机值，避免大量客户端同时重连
+fn backoff_delay(initial: Duration, max: Duration, attempt: u32) -> Duration {
+    let delay = initial.saturating_mul(1u32 << attempt.min(16)).min(max);
+    let half = delay / 2;
+    let jitter_range = (delay - half).as_nanos() as u64;
+    if jitter_range == 0 {
+        return delay;
+    }
+    let random = RandomState::new().build_hasher().finish();
+    half + Duration::from_nanos(random % (jitter_range + 1))
+}
+
+impl ClientInner {
+    fn start_connect(inner: &Arc<ClientInner>, reactor: &mut Reactor) {
+        {
+            let mut state = inner.state.lock().unwrap();
+            state.retry_timer = None;
+            if !state.active || state.connection.is_some() || state.connecting_fd.is_some() {
+                return;
+            }
+        }
+
+        let (fd, in_progress) = match socket::connect_nonblocking(&inner.addr) {
+            Ok(result) => result,
+            Err(e) => return Self::connect_failed(inner, e),
+        };
+        if !in_progress {
+            return Self::connected(inner, fd);
+        }
+
+        // 非阻塞 connect 完成后套接字变为可写，再通过 SO_ERROR 判断是否成功
+        inner.state.lock().unwrap().connecting_fd = Some(fd);
+        let handler_inner = Arc::clone(inner);
+        let result = reactor.add_handler(fd, libc::EPOLLOUT as u32, move |_events| {
+            let finish_inner = Arc::clone(&handler_inner);
+            handler_inner.event_loop.post(move |reactor| {
+                Self::finish_connect(&finish_inner, reactor, fd);
+            });
+        });
+        if let Err(e) = result {
+            inner.state.lock().unwrap().connecting_fd = None;
+            unsafe { libc::close(fd) };
+            Self::connect_failed(inner, e);
+        }
+    }
+
+    fn finish_connect(inner: &Arc<ClientInner>, reactor: &mut Reactor, fd: RawFd) {
+        {
+            let mut state = inner.state.lock().unwrap();
+            if state.connecting_fd != Some(fd) {
+                return;
+            }
+            state.connecting_fd = None;
+        }
+
+        if let Err(e) = reactor.remove_handler(fd) {
+            eprintln!("Error removing connect handler for fd {}: {}", fd, e);
+        }
+        match socket::socket_error(fd) {
+            None => Self::connected(inner, fd),
+            Some(e) => {
+                unsafe { libc::close(fd) };
+                Self::connect_failed(inner, e);
+            }
+        }
+    }
+
+    fn connected(inner: &Arc<ClientInner>, fd: RawFd) {
+        let local_addr = socket::local_addr(fd).unwrap_or(None);
+        let result = inner
+            .registry
+            .register(fd, Some(inner.addr), local_addr, inner.event_loop.clone());
+        if let Err(e) = result {
+            unsafe { libc::close(fd) };
+            Self::connect_failed(inner, e);
+        }
+    }
+
+    fn connect_failed(inner: &Arc<ClientInner>, error: io::Error) {
+        let handler = inner.state.lock().unwrap().error_handler.clone();
+        match handler {
+            Some(handler) => handler(&error),
+            None => eprintln!("Failed to connect to {}: {}", inner.addr, error),
+        }
+        Self::schedule_retry(inner);
+    }
+
+    fn schedule_retry(inner: &Arc<ClientInner>) {
+        let delay = {
+            let mut state = inner.state.lock().unwrap();
+            if !state.active || !state.reconnect {
+                return;
+            }
+            let delay = backoff_delay(state.initial_backoff, state.max_backoff, state.attempt);
+            state.attempt = state.attempt.saturating_add(1);
+            delay
+        };
+
+        let timer_inner = Arc::clone(inner);
+        inner.event_loop.post(move |reactor| {
+            let retry_inner = Arc::clone(&timer_inner);
+            let result = reactor.add_timer(delay, move |reactor| {
+                Self::start_connect(&retry_inner, reactor);
+            });
+            match result {
+                Ok(id) => timer_inner.state.lock().unwrap().retry_timer = Some(id),
+                Err(e) => eprintln!("Failed to schedule reconnect: {}", e),
+            }
+        });
+    }
+
+    fn on_connect(&self, conn: &Connection) {
+        let handler = {
+            let mut state = self.state.lock().unwrap();
+            // connect 完成前已经调用了 disconnect
+            if !state.active {
+                drop(state);
+                conn.close();
+                return;
+            }
+            state.attempt = 0;
+            state.connection = Some(conn.clone());
+            state.connect_handler.clone()
+        };
+        if let Some(handler) = handler {
+            handler(conn);
+        }
+    }
+
+    fn on_close(inner: &Arc<ClientInner>, conn: &Connection, reason: CloseReason) {
+        let handler = {
+            let mut state = inner.state.lock().unwrap();
+            if state.connection.as_ref().map(|c| c.id()) == Some(conn.id()) {
+                state.connection = None;
+            }
+            state.close_handler.clone()
+        };
+        if let Some(handler) = handler {
+            handler(conn, reason);
+        }
+        Self::schedule_retry(inner);
+    }
+}
+
+impl TcpClient {
+    pub fn new(event_loop: ReactorHandle, ip: &str, port: u16) -> io::Result<Self> {
+        let addr = socket::resolve(ip, port)?;
+        let inner = Arc::new(ClientInner {
+            addr,
+            event_loop,
+            registry: Arc::new(ConnectionRegistry::new()),
+            state: Mutex::new(ClientState {
+                connection: None,
+                connecting_fd: None,
+                retry_timer: None,
+                attempt: 0,
+                active: false,
+                reconnect: false,
+                initial_backoff: DEFAULT_INITIAL_BACKOFF,
+                max_backoff: DEFAULT_MAX_BACKOFF,
+                connect_handler: None,
+                close_handler: None,
+                error_handler: None,
+            }),
+        });
+
+        // registry 持有回调，回调里只保留弱引用，避免循环引用
+        let connect_inner: Weak<ClientInner> = Arc::downgrade(&inner);
+        let close_inner: Weak<ClientInner> = Arc::downgrade(&inner);
+        {
+            let mut handlers = inner.registry.handlers.lock().unwrap();
+            handlers.connect = Some(Arc::new(move |conn: &Connection| {
+                if let Some(inner) = connect_inner.upgrade() {
+                    inner.on_connect(conn);
+                }
+            }));
+            handlers.close = Some(Arc::new(move |conn: &Connection, reason| {
+                if let Some(inner) = close_inner.upgrade() {
+                    ClientInner::on_close(&inner, conn, reason);
+                }
+            }));
+        }
+
+        Ok(TcpClient { inner })
+    }
+
+    pub fn set_reconnect(&mut self, reconnect: bool) {
+        self.inner.state.lock().unwrap().reconnect = reconnect;
+    }
+
+    pub fn set_reconnect_backoff(&mut self, initial: Duration, max: Duration) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.initial_backoff = initial;
+        state.max_backoff = max.max(initial);
+    }
+
+    pub fn set_edge_triggered(&mut self, edge_triggered: bool) {
+        self.inner.registry.options.lock().unwrap().edge_triggered = edge_triggered;
+    }
+
+    pub fn set_read_buffer_size(&mut self, size: usize) {
+        self.inner.registry.options.lock().unwrap().read_buffer_size = size.max(1);
+    }
+
+    pub fn set_codec<C>(&mut self, codec: C)
+    where
+        C: Codec + Clone + Sync + 'static,
+    {
+        let factory = move || Box::new(codec.clone()) as Box<dyn Codec>;
+        *self.inner.registry.codec.lock().unwrap() = Some(Arc::new(factory));
+    }
+
+    pub fn set_connect_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&Connection) + Send + Sync + 'static,
+    {
+        self.inner.state.lock().unwrap().connect_handler = Some(Arc::new(handler));
+    }
+
+    pub fn set_message_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&Connection, &[u8]) + Send + Sync + 'static,
+    {
+        self.inner.registry.handlers.lock().unwrap().message = Some(Arc::new(handler));
+    }
+
+    pub fn set_close_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&Connection, CloseReason) + Send + Sync + 'static,
+    {
+        self.inner.state.lock().unwrap().close_handler = Some(Arc::new(handler));
+    }
+
+    pub fn set_connect_error_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&io::Error) + Send + Sync + 'static,
+    {
+        self.inner.state.lock().unwrap().error_handler = Some(Arc::new(handler));
+    }
+
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.inner.addr
+    }
+
+    pub fn connect(&self) {
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            state.active = true;
+            state.attempt = 0;
+        }
+        let inner = Arc::clone(&self.inner);
+        self.inner.event_loop.post(move |reactor| {
+            ClientInner::start_connect(&inner, reactor);
+        });
+    }
+
+    pub fn disconnect(&self) {
+        let connection = {
+            let mut state = self.inner.state.lock().unwrap();
+            state.active = false;
+            state.connection.take()
+        };
+        if let Some(conn) = connection {
+            conn.close();
+        }
+
+        // 取消等待中的重连定时器和正在进行的 connect
+        let inner = Arc::clone(&self.inner);
+        self.inner.event_loop.post(move |reactor| {
+            let (timer, fd) = {
+                let mut state = inner.state.lock().unwrap();
+                (state.retry_timer.take(), state.connecting_fd.take())
+            };
+            if let Some(timer) = timer {
+                reactor.cancel_timer(timer);
+            }
+            if let Some(fd) = fd {
+                let _ = reactor.remove_handler(fd);
+                unsafe { libc::close(fd) };
+            }
+        });
+    }
+
+    pub fn connection(&self) -> Option<Connection> {
+        self.inner.state.lock().unwrap().connection.clone()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection().is_some()
+    }
+
+    pub fn send(&self, data: &[u8]) -> io::Result<()> {
+        match self.connection() {
+            Some(conn) => conn.send(data),
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "client is not connected")),
+        }
+    }
+}
+
+impl Drop for TcpClient {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
@@ -0,0 +1,127 @@
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::{ServerConfig, ServerConnection};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, IoSlice, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+fn invalid_data<E: std::error::Error + Send + Sync + 'static>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+pub fn load_certified_key<P: AsRef<Path>>(cert_path: P, key_path: P) -> io::Result<Arc<CertifiedKey>> {
+    let mut reader = BufReader::new(File::open(cert_path)?);
+    let certs = rustls_pemfile::certs(&mut reader).collect::<io::Result<Vec<_>>>()?;
+    if certs.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "no certificate found in PEM file"));
+    }
+
+    let mut reader = BufReader::new(File::open(key_path)?);
+    let key = rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no private key found in PEM file"))?;
+    let signing_key = rustls::crypto::ring::default_provider()
+        .key_provider
+        .load_private_key(key)
+        .map_err(invalid_data)?;
+    Ok(Arc::new(CertifiedKey::new(certs, signing_key)))
+}
+
+// 按 SNI 选择证书，没有匹配或客户端未发送 SNI 时使用默认证书
+#[derive(Debug, Default)]
+pub struct CertResolver {
+    default: Option<Arc<CertifiedKey>>,
+    by_name: HashMap<String, Arc<CertifiedKey>>,
+}
+
+impl CertResolver {
+    pub fn new() -> Self {
+        CertResolver::default()
+    }
+
+    pub fn set_default<P: AsRef<Path>>(&mut self, cert_path: P, key_path: P) -> io::Result<()> {
+        self.default = Some(load_certified_key(cert_path, key_path)?);
+        Ok(())
+    }
+
+    pub fn add<P: AsRef<Path>>(&mut self, server_name: &str, cert_path: P, key_path: P) -> io::Result<()> {
+        let key = load_certified_key(cert_path, key_path)?;
+        self.by_name.insert(server_name.to_ascii_lowercase(), key);
+        Ok(())
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        client_hello
+            .server_name()
+            .and_then(|name| self.by_name.get(&name.to_ascii_lowercase()))
+            .or(self.default.as_ref())
+            .cloned()
+    }
+}
+
+pub fn server_config(resolver: CertResolver) -> io::Result<Arc<ServerConfig>> {
+    let config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(invalid_data)?
+        .with_no_client_auth()
+        .with_cert_resolver(Arc::new(resolver));
+    Ok(Arc::new(config))
+}
+
+pub fn server_config_from_pem<P: AsRef<Path>>(cert_path: P, key_path: P) -> io::Result<Arc<ServerConfig>> {
+    let mut resolver = CertResolver::new();
+    resolver.set_default(cert_path, key_path)?;
+    server_config(resolver)
+}
+
+pub(crate) fn accept(config: Arc<ServerConfig>) -> io::Result<ServerConnection> {
+    let mut tls = ServerConnection::new(config).map_err(invalid_data)?;
+    // 输出由连接自己的缓冲区负责限流，这里不再限制
+    tls.set_buffer_limit(None);
+    Ok(tls)
+}
+
+// 把收到的密文交给 rustls 处理，解密出的明文追加到 plaintext，返回对端是否发送了 close_notify
+pub(crate) fn read_tls(tls: &mut ServerConnection, mut ciphertext: &[u8], plaintext: &mut Vec<u8>) -> io::Result<bool> {
+    while !ciphertext.is_empty() {
+        tls.read_tls(&mut ciphertext)?;
+        let state = tls.process_new_packets().map_err(invalid_data)?;
+
+        let pending = state.plaintext_bytes_to_read();
+        if pending > 0 {
+            let start = plaintext.len();
+            plaintext.resize(start + pending, 0);
+            tls.reader().read_exact(&mut plaintext[start..])?;
+        }
+        if state.peer_has_closed() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// 取出 rustls 待发送的密文（握手消息、告警或加密后的应用数据）
+pub(crate) fn take_output(tls: &mut ServerConnection) -> Vec<u8> {
+    let mut output = Vec::new();
+    while tls.wants_write() {
+        if tls.write_tls(&mut output).is_err() {
+            break;
+        }
+    }
+    output
+}
+
+pub(crate) fn seal(tls: &mut ServerConnection, bufs: &[IoSlice]) -> io::Result<Vec<u8>> {
+    for buf in bufs {
+        tls.writer().write_all(buf)?;
+    }
+    Ok(take_output(tls))
+}
+
+pub(crate) fn close_notify(tls: &mut ServerConnection) -> Vec<u8> {
+    tls.send_close_notify();
+    take_output(tls)
+}
@@ -0,0 +1,361 @@
+use crate::core::reactor::{Reactor, ReactorHandle};
+use crate::network::socket;
+use std::io;
+use std::net::{SocketAddr, SocketAddrV6};
+use std::os::unix::io::RawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_BATCH_SIZE: usize = 16;
+const DEFAULT_MAX_DATAGRAM_SIZE: usize = 64 * 1024;
+
+type DatagramHandler = Arc<dyn Fn(SocketAddr, &[u8]) + Send + Sync>;
+
+struct UdpSocket {
+    fd: RawFd,
+    ipv6: bool,
+}
+
+impl UdpSocket {
+    // IPv6 套接字发往 IPv4 地址时要转换成 ::ffff:a.b.c.d
+    fn target(&self, addr: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+        match addr {
+            SocketAddr::V4(v4) if self.ipv6 => {
+                let mapped = SocketAddr::V6(SocketAddrV6::new(v4.ip().to_ipv6_mapped(), v4.port(), 0, 0));
+                socket::addr_to_sockaddr(&mapped)
+            }
+            _ => socket::addr_to_sockaddr(addr),
+        }
+    }
+}
+
+impl Drop for UdpSocket {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+// UDP 没有输出缓冲区，发送缓冲区满时直接返回 WouldBlock 由调用方决定是否丢弃
+#[derive(Clone)]
+pub struct UdpSender {
+    socket: Arc<UdpSocket>,
+}
+
+impl UdpSender {
+    pub fn send_to(&self, addr: SocketAddr, data: &[u8]) -> io::Result<usize> {
+        let (storage, len) = self.socket.target(&addr);
+        loop {
+            let sent = unsafe {
+                libc::sendto(
+                    self.socket.fd,
+                    data.as_ptr() as *const libc::c_void,
+                    data.len(),
+                    libc::MSG_DONTWAIT,
+                    &storage as *const _ as *const libc::sockaddr,
+                    len,
+                )
+            };
+            if sent >= 0 {
+                return Ok(sent as usize);
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    // 一次 sendmmsg 发送多个数据报，返回成功发出的数据报个数
+    pub fn send_batch(&self, datagrams: &[(SocketAddr, &[u8])]) -> io::Result<usize> {
+        let mut addrs: Vec<(libc::sockaddr_storage, libc::socklen_t)> =
+            datagrams.iter().map(|(addr, _)| self.socket.target(addr)).collect();
+        let mut iovecs: Vec<libc::iovec> = datagrams
+            .iter()
+            .map(|(_, data)| libc::iovec {
+                iov_base: data.as_ptr() as *mut libc::c_void,
+                iov_len: data.len(),
+            })
+            .collect();
+        let mut headers: Vec<libc::mmsghdr> = addrs
+            .iter_mut()
+            .zip(iovecs.iter_mut())
+            .map(|((storage, len), iov)| {
+                let mut header: libc::mmsghdr = unsafe { std::mem::zeroed() };
+                header.msg_hdr.msg_name = storage as *mut _ as *mut libc::c_void;
+                header.msg_hdr.msg_namelen = *len;
+                header.msg_hdr.msg_iov = iov;
+                header.msg_hdr.msg_iovlen = 1;
+                header
+            })
+            .collect();
+
+        let mut total = 0;
+        while total < headers.len() {
+            let sent = unsafe {
+                libc::sendmmsg(
+                    self.socket.fd,
+                    headers[total..].as_mut_ptr(),
+                    (headers.len() - total) as libc::c_uint,
+                    libc::MSG_DONTWAIT,
+                )
+            };
+            if sent == -1 {
+                let err = io::Error::last_os_error();
+                match err.kind() {
+                    io::ErrorKind::Interrupted => continue,
+                    _ if total > 0 => break,
+                    _ => return Err(err),
+                }
+            }
+            total += sent as usize;
+        }
+        Ok(total)
+    }
+}
+
+struct RecvBatch {
+    buffers: Vec<Vec<u8>>,
+    addrs: Vec<libc::sockaddr_storage>,
+    iovecs: Vec<libc::iovec>,
+    headers: Vec<libc::mmsghdr>,
+}
+
+// iovecs/headers 中的指针只指向自身持有的 buffers 和 addrs
+unsafe impl Send for RecvBatch {}
+
+impl RecvBatch {
+    fn new(batch_size: usize, datagram_size: usize) -> Self {
+        RecvBatch {
+            buffers: vec![vec![0u8; datagram_size]; batch_size],
+            addrs: vec![unsafe { std::mem::zeroed() }; batch_size],
+            iovecs: Vec::with_capacity(batch_size),
+            headers: Vec::with_capacity(batch_size),
+        }
+    }
+
+    // 每次调用前都要重置 msg_namelen，所以 header 每批重新构造
+    fn recv(&mut self, fd: RawFd) -> io::Result<usize> {
+        self.iovecs.clear();
+        self.headers.clear();
+        for buffer in self.buffers.iter_mut() {
+            self.iovecs.push(libc::iovec {
+                iov_base: buffer.as_mut_ptr() as *mut libc::c_void,
+                iov_len: buffer.len(),
+            });
+        }
+        for (addr, iov) in self.addrs.iter_mut().zip(self.iovecs.iter_mut()) {
+            let mut header: libc::mmsghdr = unsafe { std::mem::zeroed() };
+            header.msg_hdr.msg_name = addr as *mut _ as *mut libc::c_void;
+            header.msg_hdr.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+            header.msg_hdr.msg_iov = iov;
+            header.msg_hdr.msg_iovlen = 1;
+            self.headers.push(header);
+        }
+
+        loop {
+            let received = unsafe {
+                libc::recvmmsg(
+                    fd,
+                    self.headers.as_mut_ptr(),
+                    self.headers.len() as libc::c_uint,
+                    libc::MSG_DONTWAIT,
+                    std::ptr::null_mut(),
+                )
+            };
+            if received >= 0 {
+                return Ok(received as usize);
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    fn datagram(&self, index: usize) -> Option<(SocketAddr, &[u8])> {
+        let header = &self.headers[index];
+        if header.msg_hdr.msg_flags & libc::MSG_TRUNC != 0 {
+            eprintln!("Dropping truncated datagram larger than {} bytes", self.buffers[index].len());
+            return None;
+        }
+        let addr = socket::sockaddr_to_addr(&self.addrs[index], header.msg_hdr.msg_namelen)?;
+        Some((addr, &self.buffers[index][..header.msg_len as usize]))
+    }
+}
+
+struct UdpState {
+    handler: Option<DatagramHandler>,
+    batch_size: usize,
+    max_datagram_size: usize,
+}
+
+pub struct UdpServer {
+    state: Arc<Mutex<UdpState>>,
+    socket: Arc<UdpSocket>,
+    main_loop: ReactorHandle,
+    reactor: Option<Reactor>,
+    running: Arc<AtomicBool>,
+}
+
+impl UdpServer {
+    pub fn new(reactor: Reactor, ip: &str, port: u16) -> io::Result<Self> {
+        let addr = socket::resolve(ip, port)?;
+        let fd = bind_udp_socket(&addr)?;
+
+        Ok(UdpServer {
+            state: Arc::new(Mutex::new(UdpState {
+                handler: None,
+                batch_size: DEFAULT_BATCH_SIZE,
+                max_datagram_size: DEFAULT_MAX_DATAGRAM_SIZE,
+            })),
+            socket: Arc::new(UdpSocket { fd, ipv6: addr.is_ipv6() }),
+            main_loop: reactor.handle(),
+            reactor: Some(reactor),
+            running: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        socket::local_addr(self.socket.fd)?
+            .ok_or_else(|| io::Error::other("unknown socket address"))
+    }
+
+    pub fn set_batch_size(&mut self, batch_size: usize) {
+        self.state.lock().unwrap().batch_size = batch_size.max(1);
+    }
+
+    pub fn set_max_datagram_size(&mut self, size: usize) {
+        self.state.lock().unwrap().max_datagram_size = size.max(1);
+    }
+
+    pub fn set_receive_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(SocketAddr, &[u8]) + Send + Sync + 'static,
+    {
+        self.state.lock().unwrap().handler = Some(Arc::new(handler));
+    }
+
+    pub fn sender(&self) -> UdpSender {
+        UdpSender { socket: Arc::clone(&self.socket) }
+    }
+
+    pub fn send_to(&self, addr: SocketAddr, data: &[u8]) -> io::Result<usize> {
+        self.sender().send_to(addr, data)
+    }
+
+    pub fn send_batch(&self, datagrams: &[(SocketAddr, &[u8])]) -> io::Result<usize> {
+        self.sender().send_batch(datagrams)
+    }
+
+    fn handle_read(socket: &UdpSocket, batch: &mut RecvBatch, state: &Arc<Mutex<UdpState>>) {
+        let handler = state.lock().unwrap().handler.clone();
+
+        // LT 模式下读满一批就继续，不足一批说明已经读空
+        loop {
+            let received = match batch.recv(socket.fd) {
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return,
+                Err(e) => {
+                    eprintln!("Error receiving datagrams: {}", e);
+                    return;
+                }
+            };
+
+            if let Some(handler) = handler.as_ref() {
+                for index in 0..received {
+                    if let Some((addr, data)) = batch.datagram(index) {
+                        handler(addr, data);
+                    }
+                }
+            }
+            if received < batch.buffers.len() {
+                return;
+            }
+        }
+    }
+
+    pub fn start(&mut self) -> io::Result<()> {
+        self.running.store(true, Ordering::SeqCst);
+
+        let (batch_size, max_datagram_size) = {
+            let state = self.state.lock().unwrap();
+            (state.batch_size, state.max_datagram_size)
+        };
+        let state = Arc::clone(&self.state);
+        let socket = Arc::clone(&self.socket);
+        let fd = socket.fd;
+        let mut batch = RecvBatch::new(batch_size, max_datagram_size);
+
+        let read_handler = move |events: u32| {
+            if events & (libc::EPOLLIN as u32) != 0 {
+                Self::handle_read(&socket, &mut batch, &state);
+            }
+        };
+
+        match self.reactor.as_mut() {
+            Some(reactor) => reactor.add_handler(fd, libc::EPOLLIN as u32, read_handler)?,
+            None => self.main_loop.post(move |reactor| {
+                if let Err(e) = reactor.add_handler(fd, libc::EPOLLIN as u32, read_handler) {
+                    eprintln!("Failed to register UDP socket: {}", e);
+                }
+            }),
+        }
+        Ok(())
+    }
+
+    pub fn stop(&mut self) -> io::Result<()> {
+        self.running.store(false, Ordering::SeqCst);
+
+        let fd = self.socket.fd;
+        match self.reactor.as_mut() {
+            Some(reactor) => reactor.remove_handler(fd)?,
+            None => self.main_loop.post(move |reactor| {
+                let _ = reactor.remove_handler(fd);
+            }),
+        }
+        self.main_loop.stop();
+        Ok(())
+    }
+
+    // 主 reactor 只能取出一次，之后返回 None
+    pub fn get_reactor(&mut self) -> Option<Reactor> {
+        self.reactor.take()
+    }
+}
+
+impl Drop for UdpServer {
+    fn drop(&mut self) {
+        if self.running.load(Ordering::SeqCst) {
+            let _ = self.stop();
+        }
+    }
+}
+
+fn bind_udp_socket(addr: &SocketAddr) -> io::Result<RawFd> {
+    let family = match addr {
+        SocketAddr::V4(_) => libc::AF_INET,
+        SocketAddr::V6(_) => libc::AF_INET6,
+    };
+    let fd = unsafe { libc::socket(family, libc::SOCK_DGRAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC, 0) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let (storage, len) = socket::addr_to_sockaddr(addr);
+    let result = socket::set_int_option(fd, libc::SOL_SOCKET, libc::SO_REUSEADDR, 1).and_then(|_| unsafe {
+        if libc::bind(fd, &storage as *const _ as *const libc::sockaddr, len) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => Ok(fd),
+        Err(e) => {
+            unsafe { libc::close(fd) };
+            Err(e)
+        }
+    }
+}
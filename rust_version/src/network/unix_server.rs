@@ -0,0 +1,114 @@
+use crate::core::reactor::Reactor;
+use crate::network::server::Server;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_BACKLOG: i32 = libc::SOMAXCONN;
+
+pub struct Unix {
+    path: Option<PathBuf>,
+}
+
+impl Drop for Unix {
+    fn drop(&mut self) {
+        // 抽象命名空间没有文件，只需清理路径形式的套接字文件
+        if let Some(path) = self.path.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+pub type UnixServer = Server<Unix>;
+
+impl Server<Unix> {
+    pub fn bind<P: AsRef<Path>>(reactor: Reactor, path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        remove_stale_socket(path)?;
+
+        let (addr, len) = path_to_sockaddr(path.as_os_str().as_bytes(), false)?;
+        let listen_fd = bind_unix_listener(&addr, len)?;
+        let listener = Unix { path: Some(path.to_path_buf()) };
+        Ok(Server::from_listener(reactor, listen_fd, listener))
+    }
+
+    pub fn bind_abstract(reactor: Reactor, name: &[u8]) -> io::Result<Self> {
+        let (addr, len) = path_to_sockaddr(name, true)?;
+        let listen_fd = bind_unix_listener(&addr, len)?;
+        Ok(Server::from_listener(reactor, listen_fd, Unix { path: None }))
+    }
+
+    pub fn socket_path(&self) -> Option<&Path> {
+        self.listener().path.as_deref()
+    }
+}
+
+fn path_to_sockaddr(name: &[u8], abstract_name: bool) -> io::Result<(libc::sockaddr_un, libc::socklen_t)> {
+    let mut addr: libc::sockaddr_un = unsafe { std::mem::zeroed() };
+    addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+
+    // 抽象命名空间以 '\0' 开头，路径形式需要保留结尾的 '\0'
+    let offset = abstract_name as usize;
+    if name.is_empty() || offset + name.len() >= addr.sun_path.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid unix socket name length"));
+    }
+    if !abstract_name && name.contains(&0) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "unix socket path contains NUL"));
+    }
+    for (dst, src) in addr.sun_path[offset..].iter_mut().zip(name) {
+        *dst = *src as libc::c_char;
+    }
+
+    let base = std::mem::size_of::<libc::sa_family_t>();
+    let len = if abstract_name {
+        base + offset + name.len()
+    } else {
+        base + name.len() + 1
+    };
+    Ok((addr, len as libc::socklen_t))
+}
+
+// 之前的进程异常退出会留下套接字文件，只有在无人监听时才删除
+fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        ));
+    }
+
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("{} is already in use", path.display()),
+        )),
+        Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => fs::remove_file(path),
+        Err(e) => Err(e),
+    }
+}
+
+fn bind_unix_listener(addr: &libc::sockaddr_un, len: libc::socklen_t) -> io::Result<RawFd> {
+    let fd = unsafe { libc::socket(libc::AF_UNIX, libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC, 0) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    unsafe {
+        if libc::bind(fd, addr as *const _ as *const libc::sockaddr, len) == -1
+            || libc::listen(fd, DEFAULT_BACKLOG) == -1
+        {
+            let err = io::Error::last_os_error();
+            libc::close(fd);
+            return Err(err);
+        }
+    }
+    Ok(fd)
+}
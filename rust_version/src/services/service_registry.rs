@@ -12,6 +12,12 @@ pub struct ServiceRegistry {
     services: Arc<Mutex<HashMap<String, Vec<ServiceInstance>>>>,
 }
 
+impl Default for ServiceRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ServiceRegistry {
     pub fn new() -> Self {
         ServiceRegistry {
@@ -21,7 +27,7 @@ impl ServiceRegistry {
 
     pub fn register_service(&self, service_name: &str, host: String, port: u16) {
         let mut services = self.services.lock().unwrap();
-        let instances = services.entry(service_name.to_string()).or_insert_with(Vec::new);
+        let instances = services.entry(service_name.to_string()).or_default();
         instances.push(ServiceInstance { host, port });
     }
 
@@ -0,0 +1,67 @@
+// 各测试目标共用的辅助函数，不是每个目标都会用到全部函数
+#![allow(dead_code)]
+
+use rust_version::core::reactor::Reactor;
+use rust_version::messaging::rpc::RpcServer;
+use rust_version::network::server::Server;
+use rust_version::network::udp_server::UdpServer;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// 等待条件成立，超时返回 false
+pub fn wait_until<F: Fn() -> bool>(timeout: Duration, condition: F) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    condition()
+}
+
+pub trait TestServer {
+    fn start(&mut self) -> io::Result<()>;
+    fn get_reactor(&mut self) -> Option<Reactor>;
+}
+
+impl<L> TestServer for Server<L> {
+    fn start(&mut self) -> io::Result<()> {
+        Server::start(self)
+    }
+
+    fn get_reactor(&mut self) -> Option<Reactor> {
+        Server::get_reactor(self)
+    }
+}
+
+impl TestServer for UdpServer {
+    fn start(&mut self) -> io::Result<()> {
+        UdpServer::start(self)
+    }
+
+    fn get_reactor(&mut self) -> Option<Reactor> {
+        UdpServer::get_reactor(self)
+    }
+}
+
+impl TestServer for RpcServer {
+    fn start(&mut self) -> io::Result<()> {
+        RpcServer::start(self)
+    }
+
+    fn get_reactor(&mut self) -> Option<Reactor> {
+        RpcServer::get_reactor(self)
+    }
+}
+
+// 启动服务器并在后台线程运行主 reactor
+pub fn start_server<S: TestServer>(mut server: S) -> (S, thread::JoinHandle<()>) {
+    server.start().expect("Failed to start server");
+    let mut reactor = server.get_reactor().expect("Reactor has already been taken");
+    let server_thread = thread::spawn(move || {
+        reactor.run().expect("Reactor failed");
+    });
+    (server, server_thread)
+}
@@ -0,0 +1,3 @@
+pub mod test_reactor;
+pub mod test_reactor_pool;
+// test_memory_pool 和 test_connection_pool 依赖未公开的内部字段和 rand，暂不编译
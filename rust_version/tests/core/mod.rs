@@ -1,3 +0,0 @@
-pub mod test_reactor;
-pub mod test_memory_pool;
-pub mod test_connection_pool;
\ No newline at end of file
@@ -1,9 +1,10 @@
 use rust_version::core::reactor::Reactor;
 use std::io::{self, Read, Write};
-use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
-use std::sync::{Arc, Barrier};
+use std::os::unix::io::{FromRawFd, IntoRawFd, RawFd};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Barrier};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // 创建管道的辅助函数
 fn create_pipe() -> io::Result<(RawFd, RawFd)> {
@@ -47,6 +48,7 @@ fn test_basic_event_handling() {
     ).expect("Failed to add handler");
 
     // 启动 reactor 线程
+    let handle = reactor.handle();
     let reactor_thread = thread::spawn(move || {
         reactor.run().expect("Failed to run reactor");
     });
@@ -64,6 +66,8 @@ fn test_basic_event_handling() {
     assert!(received.load(std::sync::atomic::Ordering::SeqCst));
 
     // 清理
+    handle.stop();
+    reactor_thread.join().unwrap();
     unsafe {
         libc::close(read_fd);
         libc::close(write_fd);
@@ -75,7 +79,8 @@ fn test_multiple_events() {
     let (read_fd1, write_fd1) = create_pipe().expect("Failed to create first pipe");
     let (read_fd2, write_fd2) = create_pipe().expect("Failed to create second pipe");
     
-    let barrier = Arc::new(Barrier::new(3));
+    // 两个处理函数在同一个 reactor 线程上依次执行，每次只有一个与主线程会合
+    let barrier = Arc::new(Barrier::new(2));
     let received1 = Arc::new(std::sync::atomic::AtomicBool::new(false));
     let received2 = Arc::new(std::sync::atomic::AtomicBool::new(false));
     
@@ -122,6 +127,7 @@ fn test_multiple_events() {
     }
 
     // 启动 reactor 线程
+    let handle = reactor.handle();
     let reactor_thread = thread::spawn(move || {
         reactor.run().expect("Failed to run reactor");
     });
@@ -144,6 +150,8 @@ fn test_multiple_events() {
     assert!(received2.load(std::sync::atomic::Ordering::SeqCst));
 
     // 清理
+    handle.stop();
+    reactor_thread.join().unwrap();
     unsafe {
         libc::close(read_fd1);
         libc::close(write_fd1);
@@ -169,6 +177,7 @@ fn test_handler_removal() {
     // 移除处理器
     reactor.remove_handler(read_fd).expect("Failed to remove handler");
 
+    let handle = reactor.handle();
     let reactor_thread = thread::spawn(move || {
         reactor.run().expect("Failed to run reactor");
     });
@@ -184,8 +193,362 @@ fn test_handler_removal() {
     thread::sleep(Duration::from_millis(100));
 
     // 清理
+    handle.stop();
+    reactor_thread.join().unwrap();
+    unsafe {
+        libc::close(read_fd);
+        libc::close(write_fd);
+    }
+}
+
+#[test]
+fn test_one_shot_timer() {
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let (tx, rx) = mpsc::channel();
+    let start = Instant::now();
+
+    reactor.add_timer(Duration::from_millis(50), move |_| {
+        tx.send(start.elapsed()).unwrap();
+    }).expect("Failed to add timer");
+
+    let _reactor_thread = thread::spawn(move || {
+        reactor.run().expect("Failed to run reactor");
+    });
+
+    let elapsed = rx.recv_timeout(Duration::from_secs(1)).expect("Timer did not fire");
+    assert!(elapsed >= Duration::from_millis(50));
+
+    // 一次性定时器不应再次触发
+    assert!(rx.recv_timeout(Duration::from_millis(150)).is_err());
+}
+
+#[test]
+fn test_periodic_timer() {
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let count = Arc::new(AtomicUsize::new(0));
+    let count_clone = Arc::clone(&count);
+
+    reactor.add_periodic(Duration::from_millis(20), move |_| {
+        count_clone.fetch_add(1, Ordering::SeqCst);
+    }).expect("Failed to add periodic timer");
+
+    let _reactor_thread = thread::spawn(move || {
+        reactor.run().expect("Failed to run reactor");
+    });
+
+    thread::sleep(Duration::from_millis(210));
+    let fired = count.load(Ordering::SeqCst);
+    assert!(fired >= 5, "periodic timer fired only {} times", fired);
+}
+
+#[test]
+fn test_cancel_timer() {
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let fired = Arc::new(AtomicUsize::new(0));
+
+    let fired_clone = Arc::clone(&fired);
+    let cancelled = reactor.add_timer(Duration::from_millis(30), move |_| {
+        fired_clone.fetch_add(1, Ordering::SeqCst);
+    }).expect("Failed to add timer");
+
+    let fired_clone = Arc::clone(&fired);
+    reactor.add_timer(Duration::from_millis(60), move |_| {
+        fired_clone.fetch_add(10, Ordering::SeqCst);
+    }).expect("Failed to add timer");
+
+    assert!(reactor.cancel_timer(cancelled));
+    assert!(!reactor.cancel_timer(cancelled));
+
+    let _reactor_thread = thread::spawn(move || {
+        reactor.run().expect("Failed to run reactor");
+    });
+
+    thread::sleep(Duration::from_millis(150));
+    assert_eq!(fired.load(Ordering::SeqCst), 10);
+}
+
+#[test]
+fn test_timer_callback_uses_reactor() {
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let count = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+
+    // 周期定时器第三次触发时取消自己，并直接在 reactor 上再添加一个定时器
+    let count_clone = Arc::clone(&count);
+    let own_id = Arc::new(std::sync::Mutex::new(None));
+    let own_id_clone = Arc::clone(&own_id);
+    let id = reactor.add_periodic(Duration::from_millis(10), move |reactor| {
+        if count_clone.fetch_add(1, Ordering::SeqCst) + 1 == 3 {
+            let id = own_id_clone.lock().unwrap().take().unwrap();
+            assert!(reactor.cancel_timer(id));
+            let tx = tx.clone();
+            reactor.add_timer(Duration::from_millis(10), move |_| tx.send(()).unwrap())
+                .expect("Failed to add timer");
+        }
+    }).expect("Failed to add periodic timer");
+    *own_id.lock().unwrap() = Some(id);
+
+    let handle = reactor.handle();
+    let reactor_thread = thread::spawn(move || {
+        reactor.run().expect("Failed to run reactor");
+    });
+
+    rx.recv_timeout(Duration::from_secs(1)).expect("Follow-up timer did not fire");
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+
+    handle.stop();
+    reactor_thread.join().unwrap();
+}
+
+#[test]
+fn test_post_runs_on_loop_thread() {
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let handle = reactor.handle();
+    let (tx, rx) = mpsc::channel();
+
+    let reactor_thread = thread::spawn(move || {
+        let loop_thread = thread::current().id();
+        reactor.run().expect("Failed to run reactor");
+        loop_thread
+    });
+
+    for i in 0..3 {
+        let tx = tx.clone();
+        handle.post(move |_reactor| {
+            tx.send((i, thread::current().id())).unwrap();
+        });
+    }
+
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        received.push(rx.recv_timeout(Duration::from_secs(1)).expect("Task did not run"));
+    }
+
+    handle.stop();
+    let loop_thread = reactor_thread.join().unwrap();
+
+    // 任务按投递顺序在事件循环线程上执行
+    assert_eq!(received.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert!(received.iter().all(|(_, id)| *id == loop_thread));
+}
+
+#[test]
+fn test_handle_stop_is_immediate() {
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let handle = reactor.handle();
+    let (tx, rx) = mpsc::channel();
+
+    let reactor_thread = thread::spawn(move || {
+        reactor.run().expect("Failed to run reactor");
+        tx.send(Instant::now()).unwrap();
+    });
+
+    thread::sleep(Duration::from_millis(50));
+    let stop_at = Instant::now();
+    handle.stop();
+
+    let stopped_at = rx.recv_timeout(Duration::from_secs(1)).expect("Reactor did not stop");
+    assert!(stopped_at.duration_since(stop_at) < Duration::from_millis(50));
+    assert!(!handle.is_running());
+    reactor_thread.join().unwrap();
+}
+
+#[test]
+fn test_stop_before_run() {
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let handle = reactor.handle();
+    // run() 之前的 stop() 不能被 run() 覆盖
+    handle.stop();
+
+    let (tx, rx) = mpsc::channel();
+    let reactor_thread = thread::spawn(move || {
+        reactor.run().expect("Failed to run reactor");
+        tx.send(()).unwrap();
+    });
+
+    rx.recv_timeout(Duration::from_secs(1)).expect("Reactor did not stop");
+    assert!(!handle.is_running());
+    reactor_thread.join().unwrap();
+}
+
+#[test]
+fn test_run_again_after_stop() {
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let fired = Arc::new(AtomicUsize::new(0));
+
+    // 每轮都由定时器停止，第二次 run 不能因为上一轮的 stop 直接返回
+    for round in 1..=2 {
+        let fired_clone = Arc::clone(&fired);
+        reactor
+            .add_timer(Duration::from_millis(20), move |reactor| {
+                fired_clone.fetch_add(1, Ordering::SeqCst);
+                reactor.stop();
+            })
+            .expect("Failed to add timer");
+        reactor.run().expect("Failed to run reactor");
+        assert_eq!(fired.load(Ordering::SeqCst), round);
+    }
+}
+
+#[test]
+fn test_post_add_handler() {
+    let (read_fd, write_fd) = create_pipe().expect("Failed to create pipe");
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let handle = reactor.handle();
+    let (tx, rx) = mpsc::channel();
+
+    let reactor_thread = thread::spawn(move || {
+        reactor.run().expect("Failed to run reactor");
+    });
+
+    // 在其他线程中通过 handle 注册处理器
+    handle.post(move |reactor| {
+        reactor.add_handler(read_fd, libc::EPOLLIN as u32, move |_| {
+            let mut buf = [0u8; 1];
+            unsafe { libc::read(read_fd, buf.as_mut_ptr() as *mut libc::c_void, 1) };
+            tx.send(buf[0]).unwrap();
+        }).expect("Failed to add handler");
+    });
+
+    thread::sleep(Duration::from_millis(20));
+    unsafe { libc::write(write_fd, b"z".as_ptr() as *const libc::c_void, 1) };
+
+    assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), b'z');
+
+    handle.stop();
+    reactor_thread.join().unwrap();
+
+    unsafe {
+        libc::close(read_fd);
+        libc::close(write_fd);
+    }
+}
+
+#[test]
+fn test_modify_handler() {
+    let (read_fd, write_fd) = create_pipe().expect("Failed to create pipe");
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let handle = reactor.handle();
+    let (tx, rx) = mpsc::channel();
+
+    reactor.add_handler(read_fd, libc::EPOLLIN as u32, move |events| {
+        let mut buf = [0u8; 1];
+        unsafe { libc::read(read_fd, buf.as_mut_ptr() as *mut libc::c_void, 1) };
+        tx.send(events).unwrap();
+    }).expect("Failed to add handler");
+
+    // 清空事件掩码后处理器保留但不再被调用
+    reactor.modify_handler(read_fd, 0).expect("Failed to modify handler");
+
+    let reactor_thread = thread::spawn(move || {
+        reactor.run().expect("Failed to run reactor");
+    });
+
+    unsafe { libc::write(write_fd, b"x".as_ptr() as *const libc::c_void, 1) };
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+    // 恢复 EPOLLIN 后积压的数据立即触发原处理器
+    handle.post(move |reactor| {
+        reactor.modify_handler(read_fd, libc::EPOLLIN as u32).expect("Failed to modify handler");
+    });
+    let events = rx.recv_timeout(Duration::from_secs(1)).expect("Handler was not restored");
+    assert!(events & (libc::EPOLLIN as u32) != 0);
+
+    handle.stop();
+    reactor_thread.join().unwrap();
+
+    unsafe {
+        libc::close(read_fd);
+        libc::close(write_fd);
+    }
+}
+
+#[test]
+fn test_modify_unknown_handler() {
+    let (read_fd, write_fd) = create_pipe().expect("Failed to create pipe");
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+
+    let err = reactor.modify_handler(read_fd, libc::EPOLLIN as u32).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
     unsafe {
         libc::close(read_fd);
         libc::close(write_fd);
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_signal_handler() {
+    use std::os::unix::thread::JoinHandleExt;
+
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let (signal_tx, signal_rx) = mpsc::channel();
+
+    // 信号只屏蔽在事件循环线程上，用 pthread_kill 定向发送，避免影响其它测试线程
+    let reactor_thread = thread::spawn(move || {
+        let mut reactor = Reactor::new().expect("Failed to create reactor");
+        let usr1_tx = signal_tx.clone();
+        // 处理函数中可以移除自己
+        reactor
+            .add_signal_handler(libc::SIGUSR1, move |reactor| {
+                usr1_tx.send(libc::SIGUSR1).unwrap();
+                assert!(reactor.remove_signal_handler(libc::SIGUSR1).unwrap());
+            })
+            .expect("Failed to add signal handler");
+        reactor
+            .add_signal_handler(libc::SIGUSR2, move |reactor| {
+                signal_tx.send(libc::SIGUSR2).unwrap();
+                reactor.stop();
+            })
+            .expect("Failed to add signal handler");
+        ready_tx.send(()).unwrap();
+        reactor.run().expect("Failed to run reactor");
+
+        assert!(!reactor.remove_signal_handler(libc::SIGUSR1).unwrap());
+        assert!(reactor.remove_signal_handler(libc::SIGUSR2).unwrap());
+    });
+
+    ready_rx.recv_timeout(Duration::from_secs(1)).expect("Reactor not ready");
+    let thread_id = reactor_thread.as_pthread_t();
+    unsafe { libc::pthread_kill(thread_id, libc::SIGUSR1) };
+    assert_eq!(signal_rx.recv_timeout(Duration::from_secs(1)), Ok(libc::SIGUSR1));
+
+    // 第二个信号的处理函数停止事件循环
+    unsafe { libc::pthread_kill(thread_id, libc::SIGUSR2) };
+    assert_eq!(signal_rx.recv_timeout(Duration::from_secs(1)), Ok(libc::SIGUSR2));
+    reactor_thread.join().unwrap();
+}
+
+// 查询当前线程是否屏蔽了该信号
+fn signal_blocked(signal: i32) -> bool {
+    unsafe {
+        let mut current: libc::sigset_t = std::mem::zeroed();
+        libc::pthread_sigmask(libc::SIG_BLOCK, std::ptr::null(), &mut current);
+        libc::sigismember(&current, signal) == 1
+    }
+}
+
+#[test]
+fn test_drop_unblocks_signals() {
+    // 在单独的线程上修改屏蔽字，不影响其它测试线程
+    thread::spawn(|| {
+        let mut reactor = Reactor::new().expect("Failed to create reactor");
+        reactor.add_signal_handler(libc::SIGUSR1, |_| {}).expect("Failed to add signal handler");
+        assert!(signal_blocked(libc::SIGUSR1));
+
+        // 未读取的信号在解除屏蔽前被丢弃，不会按默认动作终止进程
+        unsafe { libc::pthread_kill(libc::pthread_self(), libc::SIGUSR1) };
+        drop(reactor);
+        assert!(!signal_blocked(libc::SIGUSR1));
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn test_invalid_signal() {
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let err = reactor.add_signal_handler(1000, |_| {}).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
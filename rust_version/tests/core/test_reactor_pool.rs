@@ -0,0 +1,46 @@
+use rust_version::core::reactor_pool::ReactorPool;
+use std::collections::HashSet;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_reactor_pool_round_robin() {
+    let pool = ReactorPool::new(3).expect("Failed to create reactor pool");
+    assert_eq!(pool.len(), 3);
+
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..6 {
+        let tx = tx.clone();
+        let handle = pool.next_handle().expect("Pool should not be empty");
+        handle.post(move |_| {
+            tx.send(thread::current().name().unwrap().to_string()).unwrap();
+        });
+    }
+
+    let mut names = Vec::new();
+    for _ in 0..6 {
+        names.push(rx.recv_timeout(Duration::from_secs(1)).expect("Task did not run"));
+    }
+
+    // 六个任务轮询分配到三个事件循环线程上
+    let distinct: HashSet<_> = names.iter().collect();
+    assert_eq!(distinct.len(), 3);
+    for name in distinct {
+        assert_eq!(names.iter().filter(|n| *n == name).count(), 2);
+    }
+}
+
+#[test]
+fn test_empty_reactor_pool() {
+    let pool = ReactorPool::new(0).expect("Failed to create reactor pool");
+    assert!(pool.is_empty());
+    assert!(pool.next_handle().is_none());
+}
+
+#[test]
+fn test_reactor_pool_stop() {
+    let pool = ReactorPool::new(2).expect("Failed to create reactor pool");
+    pool.stop();
+    assert!(pool.handles().iter().all(|handle| !handle.is_running()));
+}
@@ -0,0 +1,4 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+pub mod test_rpc;
@@ -0,0 +1,127 @@
+use crate::common::{start_server, wait_until};
+use rust_version::core::reactor::Reactor;
+use rust_version::messaging::rpc::{method_id, FrameHeader, RpcClient, RpcError, RpcServer, Status};
+use rust_version::messaging::{Deserialize, Deserializer, SerializationError, Serialize, Serializer};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct Pair {
+    a: i32,
+    b: i32,
+}
+
+impl Serialize for Pair {
+    fn serialize(&self, serializer: &mut Serializer) -> Result<(), SerializationError> {
+        serializer.write(&self.a)?;
+        serializer.write(&self.b)
+    }
+}
+
+impl Deserialize for Pair {
+    fn deserialize(deserializer: &mut Deserializer) -> Result<Self, SerializationError> {
+        Ok(Pair { a: deserializer.read()?, b: deserializer.read()? })
+    }
+}
+
+// 序列化总是失败的响应类型
+struct Unserializable;
+
+impl Serialize for Unserializable {
+    fn serialize(&self, _serializer: &mut Serializer) -> Result<(), SerializationError> {
+        Err(SerializationError::BufferOverflow)
+    }
+}
+
+#[test]
+fn test_frame_header_roundtrip() {
+    let header = FrameHeader { request_id: 42, method_id: method_id("add"), status: Status::InvalidRequest };
+    let frame = header.encode(b"body");
+    let (decoded, payload) = FrameHeader::decode(&frame).unwrap();
+    assert_eq!(decoded, header);
+    assert_eq!(payload, b"body");
+    assert!(FrameHeader::decode(&frame[..5]).is_err());
+    assert_ne!(method_id("add"), method_id("echo"));
+}
+
+#[test]
+fn test_rpc_calls() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = RpcServer::new(reactor, "127.0.0.1", 18114).expect("Failed to create server");
+    server.server().set_thread_num(2);
+    server.register("add", |pair: Pair| pair.a + pair.b).unwrap();
+    server.register("echo", |s: String| format!("echo: {}", s)).unwrap();
+    server
+        .register("slow", |ms: i32| {
+            thread::sleep(Duration::from_millis(ms as u64));
+            ms
+        })
+        .unwrap();
+    server.register("broken", |_: i32| Unserializable).unwrap();
+    let err = server.register("add", |x: i32| x).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client_reactor = Reactor::new().expect("Failed to create reactor");
+    let handle = client_reactor.handle();
+    let client_thread = thread::spawn(move || {
+        client_reactor.run().expect("Reactor failed");
+    });
+    let client = Arc::new(RpcClient::new(handle.clone(), "127.0.0.1", 18114).expect("Failed to create client"));
+    client.connect();
+    assert!(wait_until(Duration::from_secs(2), || client.is_connected()));
+
+    let timeout = Duration::from_secs(2);
+    let echoed: String = client.call("echo", &"hi".to_string(), timeout).unwrap();
+    assert_eq!(echoed, "echo: hi");
+
+    // 多个线程在同一个连接上并发调用，响应按 request id 对应
+    let workers: Vec<_> = (0..8)
+        .map(|i| {
+            let client = Arc::clone(&client);
+            thread::spawn(move || {
+                for j in 0..20 {
+                    let sum: i32 = client.call("add", &Pair { a: i, b: j }, timeout).unwrap();
+                    assert_eq!(sum, i + j);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    assert!(matches!(
+        client.call::<i32, i32>("missing", &1, timeout),
+        Err(RpcError::MethodNotFound(name)) if name == "missing"
+    ));
+    assert!(matches!(
+        client.call::<String, i32>("add", &"x".to_string(), timeout),
+        Err(RpcError::InvalidRequest(_))
+    ));
+    // 服务端响应序列化失败不归咎于请求
+    assert!(matches!(
+        client.call::<i32, i32>("broken", &1, timeout),
+        Err(RpcError::Internal(_))
+    ));
+
+    // 超时的调用返回 Timeout，迟到的响应被丢弃，后续调用不受影响
+    let start = Instant::now();
+    assert!(matches!(
+        client.call::<i32, i32>("slow", &300, Duration::from_millis(50)),
+        Err(RpcError::Timeout)
+    ));
+    assert!(start.elapsed() < Duration::from_millis(250));
+    let value: i32 = client.call("slow", &1, timeout).unwrap();
+    assert_eq!(value, 1);
+
+    client.disconnect();
+    assert!(wait_until(Duration::from_secs(2), || !client.is_connected()));
+    assert!(matches!(client.call::<i32, i32>("slow", &1, timeout), Err(RpcError::Io(_))));
+
+    handle.stop();
+    client_thread.join().unwrap();
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
@@ -0,0 +1,12 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+pub mod test_codec;
+pub mod test_connection;
+pub mod test_tcp_server;
+pub mod test_unix_server;
+pub mod test_udp_server;
+pub mod test_tcp_client;
+pub mod test_proxy_protocol;
+#[cfg(feature = "tls")]
+pub mod test_tls;
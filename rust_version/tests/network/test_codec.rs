@@ -0,0 +1,136 @@
+use crate::common::{start_server, wait_until};
+use rust_version::core::reactor::Reactor;
+use rust_version::network::codec::{
+    Decoder, Encoder, Endian, FixedSizeCodec, LengthPrefixedCodec, LengthWidth, LineCodec,
+};
+use rust_version::network::connection::CloseReason;
+use rust_version::network::tcp_server::TcpServer;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// 依次解码出所有完整的帧，返回帧列表和剩余的字节数
+fn decode_all<D: Decoder>(decoder: &mut D, mut src: &[u8]) -> (Vec<Vec<u8>>, usize) {
+    let mut frames = Vec::new();
+    while let Some((consumed, range)) = decoder.decode(src).expect("Failed to decode") {
+        frames.push(src[range].to_vec());
+        src = &src[consumed..];
+    }
+    (frames, src.len())
+}
+
+#[test]
+fn test_length_prefixed_codec() {
+    for (width, endian, header) in [
+        (LengthWidth::U16, Endian::Big, vec![0, 3]),
+        (LengthWidth::U16, Endian::Little, vec![3, 0]),
+        (LengthWidth::U32, Endian::Big, vec![0, 0, 0, 3]),
+        (LengthWidth::U32, Endian::Little, vec![3, 0, 0, 0]),
+    ] {
+        let mut codec = LengthPrefixedCodec::new(width, endian, 1024);
+        let mut encoded = Vec::new();
+        codec.encode(b"abc", &mut encoded).unwrap();
+        codec.encode(b"", &mut encoded).unwrap();
+        assert_eq!(&encoded[..header.len()], header.as_slice());
+
+        // 最后一帧只到达了一部分
+        encoded.extend_from_slice(&header);
+        encoded.push(b'x');
+        let (frames, remaining) = decode_all(&mut codec, &encoded);
+        assert_eq!(frames, vec![b"abc".to_vec(), Vec::new()]);
+        assert_eq!(remaining, header.len() + 1);
+    }
+
+    // 超过最大帧长度时在长度字段到达后立即报错
+    let mut codec = LengthPrefixedCodec::new(LengthWidth::U32, Endian::Big, 16);
+    assert_eq!(codec.decode(&[0, 0, 0, 17]).unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(codec.encode(&[0; 17], &mut Vec::new()).unwrap_err().kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_line_and_fixed_size_codecs() {
+    let mut codec = LineCodec::new(8);
+    let (frames, remaining) = decode_all(&mut codec, b"one\r\ntwo\n\nthr");
+    assert_eq!(frames, vec![b"one".to_vec(), b"two".to_vec(), Vec::new()]);
+    assert_eq!(remaining, 3);
+    assert_eq!(codec.decode(b"too long line").unwrap_err().kind(), ErrorKind::InvalidData);
+
+    let mut encoded = Vec::new();
+    codec.encode(b"hi", &mut encoded).unwrap();
+    assert_eq!(encoded, b"hi\n");
+    assert!(codec.encode(b"a\nb", &mut encoded).is_err());
+
+    let mut codec = FixedSizeCodec::new(4);
+    let (frames, remaining) = decode_all(&mut codec, b"abcdefghij");
+    assert_eq!(frames, vec![b"abcd".to_vec(), b"efgh".to_vec()]);
+    assert_eq!(remaining, 2);
+    assert!(codec.encode(b"abc", &mut Vec::new()).is_err());
+}
+
+#[test]
+fn test_line_codec_length_boundary() {
+    // 恰好 max_line_length 字节的行，分隔符分两次到达，两种结尾都应接受
+    for terminator in [&b"\n"[..], &b"\r\n"[..]] {
+        let mut codec = LineCodec::new(8);
+        let mut line = b"12345678".to_vec();
+        line.extend_from_slice(&terminator[..terminator.len() - 1]);
+        assert!(codec.decode(&line).unwrap().is_none());
+        line.push(b'\n');
+        let (frames, remaining) = decode_all(&mut codec, &line);
+        assert_eq!(frames, vec![b"12345678".to_vec()]);
+        assert_eq!(remaining, 0);
+
+        // 多一个字节就超限
+        let mut codec = LineCodec::new(8);
+        let mut line = b"123456789".to_vec();
+        line.extend_from_slice(terminator);
+        assert_eq!(codec.decode(&line).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn test_server_codec_framing() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18113).expect("Failed to create server");
+    server.set_codec(LengthPrefixedCodec::new(LengthWidth::U16, Endian::Big, 64));
+
+    let frames = Arc::new(Mutex::new(Vec::new()));
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let frames_clone = Arc::clone(&frames);
+    let reasons_clone = Arc::clone(&reasons);
+    // 每个完整的帧调用一次，回显时重新编码
+    server.set_message_handler(move |conn, frame| {
+        frames_clone.lock().unwrap().push(frame.to_vec());
+        conn.send_frame(frame).expect("Failed to send frame");
+    });
+    server.set_close_handler(move |_conn, reason| {
+        reasons_clone.lock().unwrap().push(reason);
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18113").expect("Failed to connect");
+    // 两个帧粘在一起，第三个帧拆成多次发送
+    client.write_all(b"\x00\x05hello\x00\x01!\x00").expect("Failed to write");
+    thread::sleep(Duration::from_millis(50));
+    client.write_all(b"\x03ab").expect("Failed to write");
+    thread::sleep(Duration::from_millis(50));
+    client.write_all(b"c").expect("Failed to write");
+
+    let expected = b"\x00\x05hello\x00\x01!\x00\x03abc";
+    let mut echoed = vec![0u8; expected.len()];
+    client.read_exact(&mut echoed).expect("Failed to read");
+    assert_eq!(echoed, expected);
+    assert_eq!(*frames.lock().unwrap(), vec![b"hello".to_vec(), b"!".to_vec(), b"abc".to_vec()]);
+
+    // 超长帧导致连接关闭
+    client.write_all(b"\x01\x00").expect("Failed to write");
+    let mut buf = Vec::new();
+    client.read_to_end(&mut buf).expect("Failed to read");
+    assert!(wait_until(Duration::from_secs(2), || reasons.lock().unwrap().len() == 1));
+    assert_eq!(reasons.lock().unwrap()[0], CloseReason::Error(ErrorKind::InvalidData));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
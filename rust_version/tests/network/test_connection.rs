@@ -0,0 +1,346 @@
+use crate::common::{start_server, wait_until};
+use rust_version::core::reactor::Reactor;
+use rust_version::network::connection::{CloseReason, Connection};
+use rust_version::network::tcp_server::TcpServer;
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_connection_callbacks() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18089).expect("Failed to create server");
+    server.set_thread_num(2);
+
+    let peers: Arc<Mutex<Vec<Option<SocketAddr>>>> = Arc::new(Mutex::new(Vec::new()));
+    let closed = Arc::new(Mutex::new(Vec::new()));
+    let peers_clone = Arc::clone(&peers);
+    let closed_clone = Arc::clone(&closed);
+
+    server.set_connect_handler(move |conn: &Connection| {
+        assert_eq!(conn.local_addr().map(|addr| addr.port()), Some(18089));
+        peers_clone.lock().unwrap().push(conn.peer_addr());
+        conn.set_user_data(0usize);
+    });
+    // 回显数据，并在每个连接的 user data 中累计收到的字节数
+    server.set_message_handler(|conn, data| {
+        conn.with_user_data(|count: &mut usize| *count += data.len());
+        conn.send(data).expect("Failed to echo");
+    });
+    server.set_close_handler(move |conn, reason| {
+        assert_eq!(reason, CloseReason::PeerEof);
+        closed_clone.lock().unwrap().push((conn.id(), conn.take_user_data::<usize>()));
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18089").expect("Failed to connect");
+    client.write_all(b"hello").expect("Failed to write");
+    let mut echoed = [0u8; 5];
+    client.read_exact(&mut echoed).expect("Failed to read");
+    assert_eq!(&echoed, b"hello");
+
+    assert_eq!(*peers.lock().unwrap(), vec![Some(client.local_addr().unwrap())]);
+    assert_eq!(server.connection_count(), 1);
+
+    drop(client);
+    assert!(wait_until(Duration::from_secs(2), || closed.lock().unwrap().len() == 1));
+    assert_eq!(closed.lock().unwrap()[0].1, Some(5));
+    assert_eq!(server.connection_count(), 0);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_connection_close_from_handler() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18090).expect("Failed to create server");
+
+    let ids = Arc::new(Mutex::new(Vec::new()));
+    let ids_clone = Arc::clone(&ids);
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let reasons_clone = Arc::clone(&reasons);
+    server.set_close_handler(move |_conn, reason| {
+        reasons_clone.lock().unwrap().push(reason);
+    });
+    server.set_message_handler(move |conn, _data| {
+        ids_clone.lock().unwrap().push(conn.id());
+        conn.close();
+        assert!(conn.is_closed());
+        assert!(conn.send(b"late").is_err());
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    // 每个连接的 id 都不同
+    for _ in 0..2 {
+        let mut client = TcpStream::connect("127.0.0.1:18090").expect("Failed to connect");
+        client.write_all(b"bye").expect("Failed to write");
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).expect("Failed to read");
+        assert!(buf.is_empty());
+    }
+
+    let ids = ids.lock().unwrap();
+    assert_eq!(ids.len(), 2);
+    assert_ne!(ids[0], ids[1]);
+    assert!(wait_until(Duration::from_secs(2), || reasons.lock().unwrap().len() == 2));
+    assert_eq!(*reasons.lock().unwrap(), vec![CloseReason::Local, CloseReason::Local]);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_close_reason_rdhup_and_shutdown() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18091).expect("Failed to create server");
+    server.set_thread_num(1);
+
+    let connected: Arc<Mutex<Vec<Connection>>> = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let connected_clone = Arc::clone(&connected);
+    let received_clone = Arc::clone(&received);
+    let reasons_clone = Arc::clone(&reasons);
+    // 第一个连接暂停读，对端半关闭时数据还在套接字缓冲区中
+    server.set_connect_handler(move |conn| {
+        let mut connected = connected_clone.lock().unwrap();
+        if connected.is_empty() {
+            conn.pause_read().expect("Failed to pause read");
+        }
+        connected.push(conn.clone());
+    });
+    server.set_message_handler(move |_conn, data| {
+        received_clone.lock().unwrap().extend_from_slice(data);
+    });
+    server.set_close_handler(move |conn, reason| {
+        reasons_clone.lock().unwrap().push((conn.id(), reason));
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let mut half_closed = TcpStream::connect("127.0.0.1:18091").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || connected.lock().unwrap().len() == 1));
+    half_closed.write_all(b"before half close").expect("Failed to write");
+    half_closed.shutdown(Shutdown::Write).expect("Failed to shutdown");
+
+    // 暂停期间不会因为 EPOLLRDHUP 关闭连接
+    thread::sleep(Duration::from_millis(100));
+    assert!(reasons.lock().unwrap().is_empty());
+    assert!(received.lock().unwrap().is_empty());
+
+    // 恢复读后先收到剩余数据，再读到 EOF
+    let first = connected.lock().unwrap()[0].clone();
+    first.resume_read().expect("Failed to resume read");
+    assert!(wait_until(Duration::from_secs(2), || reasons.lock().unwrap().len() == 1));
+    assert_eq!(*received.lock().unwrap(), b"before half close");
+    assert_eq!(reasons.lock().unwrap()[0], (first.id(), CloseReason::PeerEof));
+
+    let _client = TcpStream::connect("127.0.0.1:18091").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || connected.lock().unwrap().len() == 2));
+
+    // 停止服务器时剩余连接以 ServerShutdown 关闭
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+    let second = connected.lock().unwrap()[1].id();
+    assert_eq!(reasons.lock().unwrap()[1], (second, CloseReason::ServerShutdown));
+}
+
+#[test]
+fn test_half_close_delivers_buffered_data() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18122).expect("Failed to create server");
+    server.set_thread_num(1);
+    // LT 模式下每次事件只读一小块，对端半关闭时缓冲区中仍有数据
+    server.set_read_buffer_size(16);
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let received_clone = Arc::clone(&received);
+    let reasons_clone = Arc::clone(&reasons);
+    server.set_message_handler(move |_conn, data| {
+        received_clone.lock().unwrap().extend_from_slice(data);
+    });
+    server.set_close_handler(move |_conn, reason| {
+        reasons_clone.lock().unwrap().push(reason);
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let payload: Vec<u8> = (0..4096).map(|i| (i % 251) as u8).collect();
+    let mut client = TcpStream::connect("127.0.0.1:18122").expect("Failed to connect");
+    client.write_all(&payload).expect("Failed to write");
+    client.shutdown(Shutdown::Write).expect("Failed to shutdown");
+
+    assert!(wait_until(Duration::from_secs(2), || reasons.lock().unwrap().len() == 1));
+    assert!(*received.lock().unwrap() == payload);
+    assert_eq!(reasons.lock().unwrap()[0], CloseReason::PeerEof);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_half_close_flushes_pending_output() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18123).expect("Failed to create server");
+    server.set_thread_num(1);
+
+    // 响应远大于套接字缓冲区，对端半关闭时大部分还在输出缓冲区中
+    let response: Arc<Vec<u8>> = Arc::new((0..8 * 1024 * 1024).map(|i| (i % 251) as u8).collect());
+    let response_clone = Arc::clone(&response);
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let reasons_clone = Arc::clone(&reasons);
+    server.set_message_handler(move |conn, _data| {
+        conn.send(&response_clone).expect("Failed to send");
+    });
+    server.set_close_handler(move |_conn, reason| {
+        reasons_clone.lock().unwrap().push(reason);
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18123").expect("Failed to connect");
+    client.write_all(b"GET").expect("Failed to write");
+    client.shutdown(Shutdown::Write).expect("Failed to shutdown");
+    // 等服务器处理完半关闭再开始读
+    thread::sleep(Duration::from_millis(100));
+
+    let mut received = Vec::new();
+    client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    client.read_to_end(&mut received).expect("Failed to read");
+    assert_eq!(received.len(), response.len());
+    assert!(received == *response);
+    assert!(wait_until(Duration::from_secs(2), || reasons.lock().unwrap().len() == 1));
+    assert_eq!(reasons.lock().unwrap()[0], CloseReason::PeerEof);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+// 启动服务器并记录所有关闭原因
+fn start_timeout_server<F>(port: u16, configure: F) -> (TcpServer, thread::JoinHandle<()>, Arc<Mutex<Vec<CloseReason>>>)
+where
+    F: FnOnce(&mut TcpServer),
+{
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", port).expect("Failed to create server");
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let reasons_clone = Arc::clone(&reasons);
+    server.set_close_handler(move |_conn, reason| {
+        reasons_clone.lock().unwrap().push(reason);
+    });
+    configure(&mut server);
+    let (server, server_thread) = start_server(server);
+    (server, server_thread, reasons)
+}
+
+#[test]
+fn test_first_byte_timeout() {
+    let (mut server, server_thread, reasons) = start_timeout_server(18103, |server| {
+        server.set_first_byte_timeout(Some(Duration::from_millis(100)));
+    });
+
+    let mut active = TcpStream::connect("127.0.0.1:18103").expect("Failed to connect");
+    active.write_all(b"hi").expect("Failed to write");
+    let _silent = TcpStream::connect("127.0.0.1:18103").expect("Failed to connect");
+
+    // 只有没发过数据的连接会被关闭
+    assert!(wait_until(Duration::from_secs(2), || reasons.lock().unwrap().len() == 1));
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(*reasons.lock().unwrap(), vec![CloseReason::FirstByteTimeout]);
+    assert_eq!(server.connection_count(), 1);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_idle_timeout() {
+    let (mut server, server_thread, reasons) = start_timeout_server(18104, |server| {
+        server.set_idle_timeout(Some(Duration::from_millis(200)));
+    });
+
+    // 持续有数据的连接不会超时
+    let mut client = TcpStream::connect("127.0.0.1:18104").expect("Failed to connect");
+    for _ in 0..8 {
+        client.write_all(b".").expect("Failed to write");
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert!(reasons.lock().unwrap().is_empty());
+
+    assert!(wait_until(Duration::from_secs(2), || reasons.lock().unwrap().len() == 1));
+    assert_eq!(reasons.lock().unwrap()[0], CloseReason::IdleTimeout);
+    let mut buf = Vec::new();
+    client.read_to_end(&mut buf).expect("Failed to read");
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_write_stall_timeout() {
+    let (mut server, server_thread, reasons) = start_timeout_server(18105, |server| {
+        server.set_write_stall_timeout(Some(Duration::from_millis(150)));
+        server.set_connect_handler(|conn| {
+            conn.send(&vec![0u8; 16 * 1024 * 1024]).expect("Failed to send");
+        });
+    });
+
+    // 客户端不读取，服务端输出缓冲区一直无法写出
+    let _client = TcpStream::connect("127.0.0.1:18105").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || reasons.lock().unwrap().len() == 1));
+    assert_eq!(reasons.lock().unwrap()[0], CloseReason::WriteStallTimeout);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_graceful_shutdown_drains_connections() {
+    let (server, server_thread, reasons) = start_timeout_server(18106, |server| {
+        server.set_thread_num(1);
+        server.set_shutdown_handler(|conn| {
+            conn.send(b"bye").expect("Failed to send");
+        });
+    });
+
+    let mut client = TcpStream::connect("127.0.0.1:18106").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || server.connection_count() == 1));
+
+    let start = Instant::now();
+    let shutdown_thread = thread::spawn(move || {
+        let mut server = server;
+        server.shutdown(Duration::from_secs(5)).expect("Failed to shutdown");
+        server
+    });
+
+    // 先收到通知数据，写端被半关闭后读到 EOF，客户端随后关闭
+    let mut buf = Vec::new();
+    client.read_to_end(&mut buf).expect("Failed to read");
+    assert_eq!(buf, b"bye");
+    drop(client);
+
+    let _server = shutdown_thread.join().unwrap();
+    server_thread.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(*reasons.lock().unwrap(), vec![CloseReason::ServerShutdown]);
+}
+
+#[test]
+fn test_graceful_shutdown_force_closes_after_deadline() {
+    let (mut server, server_thread, reasons) = start_timeout_server(18107, |_server| {});
+
+    let mut client = TcpStream::connect("127.0.0.1:18107").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || server.connection_count() == 1));
+
+    // 客户端不关闭连接，宽限期结束后被强制关闭
+    let start = Instant::now();
+    server.shutdown(Duration::from_millis(200)).expect("Failed to shutdown");
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    server_thread.join().unwrap();
+
+    let mut buf = Vec::new();
+    client.read_to_end(&mut buf).expect("Failed to read");
+    assert!(buf.is_empty());
+    assert_eq!(*reasons.lock().unwrap(), vec![CloseReason::ServerShutdown]);
+}
@@ -0,0 +1,131 @@
+use crate::common::{start_server, wait_until};
+use rust_version::core::reactor::Reactor;
+use rust_version::network::connection::CloseReason;
+use rust_version::network::tcp_server::TcpServer;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+type Peers = Arc<Mutex<Vec<Option<SocketAddr>>>>;
+type Reasons = Arc<Mutex<Vec<CloseReason>>>;
+
+// connect 时记录 peer_addr，消息前面加上 peer_addr 回显
+fn start_proxy_server(port: u16) -> (TcpServer, thread::JoinHandle<()>, Peers, Reasons) {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", port).expect("Failed to create server");
+    server.set_proxy_protocol(true);
+
+    let peers = Arc::new(Mutex::new(Vec::new()));
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let peers_clone = Arc::clone(&peers);
+    let reasons_clone = Arc::clone(&reasons);
+    server.set_connect_handler(move |conn| {
+        peers_clone.lock().unwrap().push(conn.peer_addr());
+    });
+    server.set_message_handler(|conn, data| {
+        conn.send(data).expect("Failed to echo");
+    });
+    server.set_close_handler(move |_conn, reason| {
+        reasons_clone.lock().unwrap().push(reason);
+    });
+
+    let (server, server_thread) = start_server(server);
+    (server, server_thread, peers, reasons)
+}
+
+fn read_echo(client: &mut TcpStream, expected: &[u8]) {
+    let mut echoed = vec![0u8; expected.len()];
+    client.read_exact(&mut echoed).expect("Failed to read");
+    assert_eq!(echoed, expected);
+}
+
+fn v2_header(command: u8, family: u8, addresses: &[u8]) -> Vec<u8> {
+    let mut header = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+    header.push(0x20 | command);
+    header.push(family);
+    header.extend_from_slice(&(addresses.len() as u16).to_be_bytes());
+    header.extend_from_slice(addresses);
+    header
+}
+
+#[test]
+fn test_proxy_protocol_v1() {
+    let (mut server, server_thread, peers, _reasons) = start_proxy_server(18111);
+
+    // 头部和数据在同一个包里
+    let mut client = TcpStream::connect("127.0.0.1:18111").expect("Failed to connect");
+    client
+        .write_all(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 443\r\nhello")
+        .expect("Failed to write");
+    read_echo(&mut client, b"hello");
+
+    // 头部分多次到达
+    let mut split = TcpStream::connect("127.0.0.1:18111").expect("Failed to connect");
+    split.write_all(b"PROXY TCP6 2001:db8::1 ").expect("Failed to write");
+    thread::sleep(Duration::from_millis(50));
+    split.write_all(b"2001:db8::2 40000 443\r").expect("Failed to write");
+    thread::sleep(Duration::from_millis(50));
+    split.write_all(b"\nworld").expect("Failed to write");
+    read_echo(&mut split, b"world");
+
+    // UNKNOWN 沿用套接字地址
+    let mut unknown = TcpStream::connect("127.0.0.1:18111").expect("Failed to connect");
+    unknown.write_all(b"PROXY UNKNOWN\r\n!").expect("Failed to write");
+    read_echo(&mut unknown, b"!");
+
+    assert_eq!(
+        *peers.lock().unwrap(),
+        vec![
+            Some("192.0.2.1:56324".parse().unwrap()),
+            Some("[2001:db8::1]:40000".parse().unwrap()),
+            Some(unknown.local_addr().unwrap()),
+        ]
+    );
+    // 连接数限制仍按真实的套接字地址统计
+    assert_eq!(server.connections_from(IpAddr::V4(Ipv4Addr::LOCALHOST)), 3);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_proxy_protocol_v2_and_invalid_header() {
+    let (mut server, server_thread, peers, reasons) = start_proxy_server(18112);
+
+    let mut addresses = vec![203, 0, 113, 7, 10, 0, 0, 1];
+    addresses.extend_from_slice(&5555u16.to_be_bytes());
+    addresses.extend_from_slice(&80u16.to_be_bytes());
+    // 附带的 TLV 被忽略
+    addresses.extend_from_slice(&[0x04, 0x00, 0x01, 0xff]);
+    let mut client = TcpStream::connect("127.0.0.1:18112").expect("Failed to connect");
+    let mut packet = v2_header(0x1, 0x11, &addresses);
+    packet.extend_from_slice(b"v2");
+    client.write_all(&packet).expect("Failed to write");
+    read_echo(&mut client, b"v2");
+
+    // LOCAL 命令来自代理自身
+    let mut local = TcpStream::connect("127.0.0.1:18112").expect("Failed to connect");
+    local.write_all(&v2_header(0x0, 0x00, &[])).expect("Failed to write");
+    local.write_all(b"ok").expect("Failed to write");
+    read_echo(&mut local, b"ok");
+
+    assert_eq!(
+        *peers.lock().unwrap(),
+        vec![Some("203.0.113.7:5555".parse().unwrap()), Some(local.local_addr().unwrap())]
+    );
+
+    // 没有 PROXY 头部的连接直接关闭，不会通知 connect
+    let mut plain = TcpStream::connect("127.0.0.1:18112").expect("Failed to connect");
+    plain.write_all(b"GET / HTTP/1.1\r\n\r\n").expect("Failed to write");
+    let mut buf = Vec::new();
+    plain.read_to_end(&mut buf).expect("Failed to read");
+    assert!(buf.is_empty());
+    assert!(wait_until(Duration::from_secs(2), || reasons.lock().unwrap().len() == 1));
+    assert_eq!(reasons.lock().unwrap()[0], CloseReason::Error(std::io::ErrorKind::InvalidData));
+    assert_eq!(peers.lock().unwrap().len(), 2);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
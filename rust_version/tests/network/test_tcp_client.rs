@@ -0,0 +1,110 @@
+use crate::common::{start_server, wait_until};
+use rust_version::core::reactor::{Reactor, ReactorHandle};
+use rust_version::network::connection::CloseReason;
+use rust_version::network::tcp_client::TcpClient;
+use rust_version::network::tcp_server::TcpServer;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+fn start_echo_server(port: u16) -> (TcpServer, thread::JoinHandle<()>) {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", port).expect("Failed to create server");
+    server.set_message_handler(|conn, data| {
+        conn.send(data).expect("Failed to echo");
+    });
+    start_server(server)
+}
+
+// 客户端使用独立的事件循环线程
+fn start_client_loop() -> (ReactorHandle, thread::JoinHandle<()>) {
+    let mut reactor = Reactor::new().expect("Failed to create reactor");
+    let handle = reactor.handle();
+    let loop_thread = thread::spawn(move || {
+        reactor.run().expect("Reactor failed");
+    });
+    (handle, loop_thread)
+}
+
+#[test]
+fn test_client_echo_and_disconnect() {
+    let (mut server, server_thread) = start_echo_server(18098);
+    let (handle, loop_thread) = start_client_loop();
+
+    let mut client = TcpClient::new(handle.clone(), "127.0.0.1", 18098).expect("Failed to create client");
+    let echoed = Arc::new(Mutex::new(Vec::new()));
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let echoed_clone = Arc::clone(&echoed);
+    let reasons_clone = Arc::clone(&reasons);
+    client.set_connect_handler(|conn| {
+        conn.send(b"hello").expect("Failed to send");
+    });
+    client.set_message_handler(move |_conn, data| {
+        echoed_clone.lock().unwrap().extend_from_slice(data);
+    });
+    client.set_close_handler(move |_conn, reason| {
+        reasons_clone.lock().unwrap().push(reason);
+    });
+    client.connect();
+
+    assert!(wait_until(Duration::from_secs(2), || *echoed.lock().unwrap() == b"hello"));
+    assert!(client.is_connected());
+    client.send(b" again").expect("Failed to send");
+    assert!(wait_until(Duration::from_secs(2), || *echoed.lock().unwrap() == b"hello again"));
+
+    client.disconnect();
+    assert!(wait_until(Duration::from_secs(2), || reasons.lock().unwrap().len() == 1));
+    assert_eq!(reasons.lock().unwrap()[0], CloseReason::Local);
+    assert!(!client.is_connected());
+    assert!(client.send(b"late").is_err());
+
+    handle.stop();
+    loop_thread.join().unwrap();
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_client_reconnect_with_backoff() {
+    let (handle, loop_thread) = start_client_loop();
+
+    let mut client = TcpClient::new(handle.clone(), "127.0.0.1", 18099).expect("Failed to create client");
+    client.set_reconnect(true);
+    client.set_reconnect_backoff(Duration::from_millis(10), Duration::from_millis(50));
+
+    let failures = Arc::new(AtomicUsize::new(0));
+    let connects = Arc::new(AtomicUsize::new(0));
+    let failures_clone = Arc::clone(&failures);
+    let connects_clone = Arc::clone(&connects);
+    client.set_connect_error_handler(move |e| {
+        assert_eq!(e.kind(), std::io::ErrorKind::ConnectionRefused);
+        failures_clone.fetch_add(1, Ordering::SeqCst);
+    });
+    client.set_connect_handler(move |_conn| {
+        connects_clone.fetch_add(1, Ordering::SeqCst);
+    });
+    client.connect();
+
+    // 服务端未启动时连接被拒绝，客户端持续重试
+    assert!(wait_until(Duration::from_secs(2), || failures.load(Ordering::SeqCst) >= 3));
+    assert_eq!(connects.load(Ordering::SeqCst), 0);
+
+    let (mut server, server_thread) = start_echo_server(18099);
+    assert!(wait_until(Duration::from_secs(2), || connects.load(Ordering::SeqCst) == 1));
+
+    // 服务端重启后客户端自动重连
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+    drop(server);
+    assert!(wait_until(Duration::from_secs(2), || !client.is_connected()));
+
+    let (mut server, server_thread) = start_echo_server(18099);
+    assert!(wait_until(Duration::from_secs(3), || connects.load(Ordering::SeqCst) == 2));
+
+    client.disconnect();
+    handle.stop();
+    loop_thread.join().unwrap();
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
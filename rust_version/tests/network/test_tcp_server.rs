@@ -0,0 +1,695 @@
+use crate::common::{start_server, wait_until};
+use rust_version::core::reactor::Reactor;
+use rust_version::network::tcp_server::{ListenOptions, TcpServer};
+use std::collections::HashSet;
+use std::io::{IoSlice, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpStream};
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// 记录最近一次收到数据的客户端 fd
+fn track_client_fd(server: &mut TcpServer) -> Arc<AtomicI32> {
+    let client_fd = Arc::new(AtomicI32::new(-1));
+    let client_fd_clone = Arc::clone(&client_fd);
+    server.set_receive_handler(move |fd, _data, _len| {
+        client_fd_clone.store(fd, Ordering::SeqCst);
+    });
+    client_fd
+}
+
+#[test]
+fn test_multi_reactor_receive() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18081).expect("Failed to create server");
+    server.set_thread_num(2);
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let received_clone = Arc::clone(&received);
+    server.set_receive_handler(move |_fd, data, _len| {
+        let name = thread::current().name().unwrap_or("").to_string();
+        received_clone.lock().unwrap().push((name, data.to_vec()));
+    });
+
+    let (mut server, server_thread) = start_server(server);
+
+    let mut clients = Vec::new();
+    for _ in 0..4 {
+        clients.push(TcpStream::connect("127.0.0.1:18081").expect("Failed to connect"));
+    }
+    for client in &mut clients {
+        client.write_all(b"ping").expect("Failed to write");
+    }
+
+    assert!(wait_until(Duration::from_secs(2), || received.lock().unwrap().len() == 4));
+
+    // 连接被轮询分配到两个工作线程，主线程只负责 accept
+    let received = received.lock().unwrap();
+    let threads: HashSet<_> = received.iter().map(|(name, _)| name.clone()).collect();
+    assert_eq!(threads.len(), 2);
+    assert!(threads.iter().all(|name| name.starts_with("reactor-")));
+    assert!(received.iter().all(|(_, data)| data == b"ping"));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_single_reactor_receive() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18082).expect("Failed to create server");
+
+    let total = Arc::new(Mutex::new(0usize));
+    let total_clone = Arc::clone(&total);
+    server.set_receive_handler(move |_fd, _data, len| {
+        *total_clone.lock().unwrap() += len;
+    });
+
+    let (mut server, server_thread) = start_server(server);
+    // 主 reactor 已经被取出，再次获取返回 None
+    assert!(server.get_reactor().is_none());
+
+    let mut client = TcpStream::connect("127.0.0.1:18082").expect("Failed to connect");
+    client.write_all(b"hello").expect("Failed to write");
+
+    assert!(wait_until(Duration::from_secs(2), || *total.lock().unwrap() == 5));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_edge_triggered_full_drain() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18083).expect("Failed to create server");
+    server.set_edge_triggered(true);
+    server.set_read_buffer_size(4096);
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let batches = Arc::new(Mutex::new(0usize));
+    let received_clone = Arc::clone(&received);
+    let batches_clone = Arc::clone(&batches);
+    server.set_receive_handler(move |_fd, data, len| {
+        assert_eq!(data.len(), len);
+        received_clone.lock().unwrap().extend_from_slice(data);
+        *batches_clone.lock().unwrap() += 1;
+    });
+
+    let (mut server, server_thread) = start_server(server);
+
+    let payload: Vec<u8> = (0..256 * 1024).map(|i| (i % 251) as u8).collect();
+    let mut client = TcpStream::connect("127.0.0.1:18083").expect("Failed to connect");
+    client.write_all(&payload).expect("Failed to write");
+
+    assert!(wait_until(Duration::from_secs(2), || received.lock().unwrap().len() == payload.len()));
+    assert_eq!(*received.lock().unwrap(), payload);
+    // 每个事件连续读取多次，批次数远小于按 4096 字节单次读取的次数
+    assert!(*batches.lock().unwrap() < payload.len() / 4096);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_edge_triggered_read_limit() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18124).expect("Failed to create server");
+    server.set_edge_triggered(true);
+    server.set_read_buffer_size(1024);
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let largest = Arc::new(AtomicUsize::new(0));
+    let received_clone = Arc::clone(&received);
+    let largest_clone = Arc::clone(&largest);
+    server.set_receive_handler(move |_fd, data, _len| {
+        received_clone.lock().unwrap().extend_from_slice(data);
+        largest_clone.fetch_max(data.len(), Ordering::SeqCst);
+    });
+
+    let (mut server, server_thread) = start_server(server);
+
+    let payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    let mut client = TcpStream::connect("127.0.0.1:18124").expect("Failed to connect");
+    client.write_all(&payload).expect("Failed to write");
+
+    // 每个事件最多读 16 次，超出的部分重新注册后在下一轮读完
+    assert!(wait_until(Duration::from_secs(5), || received.lock().unwrap().len() == payload.len()));
+    assert!(*received.lock().unwrap() == payload);
+    assert!(largest.load(Ordering::SeqCst) <= 16 * 1024);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_level_triggered_read_buffer_size() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18084).expect("Failed to create server");
+    server.set_read_buffer_size(16);
+
+    let chunks = Arc::new(Mutex::new(Vec::new()));
+    let chunks_clone = Arc::clone(&chunks);
+    server.set_receive_handler(move |_fd, data, _len| {
+        chunks_clone.lock().unwrap().push(data.to_vec());
+    });
+
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18084").expect("Failed to connect");
+    client.write_all(&[7u8; 64]).expect("Failed to write");
+
+    assert!(wait_until(Duration::from_secs(2), || {
+        chunks.lock().unwrap().iter().map(|c| c.len()).sum::<usize>() == 64
+    }));
+    assert!(chunks.lock().unwrap().iter().all(|c| c.len() <= 16));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_buffered_send() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18085).expect("Failed to create server");
+    server.set_thread_num(1);
+    let client_fd = track_client_fd(&mut server);
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18085").expect("Failed to connect");
+    client.write_all(b"go").expect("Failed to write");
+    assert!(wait_until(Duration::from_secs(2), || client_fd.load(Ordering::SeqCst) != -1));
+    let fd = client_fd.load(Ordering::SeqCst);
+
+    // 一次性发送远超 socket 缓冲区的数据，剩余部分进入输出缓冲区
+    let payload: Vec<u8> = (0..8 * 1024 * 1024).map(|i| (i % 253) as u8).collect();
+    assert_eq!(server.send(fd, &payload).expect("Failed to send"), payload.len());
+    assert!(server.pending_bytes(fd).unwrap() > 0);
+
+    let mut received = vec![0u8; payload.len()];
+    client.read_exact(&mut received).expect("Failed to read");
+    assert_eq!(received, payload);
+    assert!(wait_until(Duration::from_secs(2), || server.pending_bytes(fd).unwrap() == 0));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_write_watermarks() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18086).expect("Failed to create server");
+    server.set_write_watermarks(1024, 256 * 1024);
+
+    let high = Arc::new(AtomicUsize::new(0));
+    let low = Arc::new(AtomicUsize::new(0));
+    let high_clone = Arc::clone(&high);
+    let low_clone = Arc::clone(&low);
+    server.set_high_watermark_handler(move |_conn, buffered| {
+        assert!(buffered >= 256 * 1024);
+        high_clone.fetch_add(1, Ordering::SeqCst);
+    });
+    server.set_low_watermark_handler(move |_conn| {
+        low_clone.fetch_add(1, Ordering::SeqCst);
+    });
+    let client_fd = track_client_fd(&mut server);
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18086").expect("Failed to connect");
+    client.write_all(b"go").expect("Failed to write");
+    assert!(wait_until(Duration::from_secs(2), || client_fd.load(Ordering::SeqCst) != -1));
+    let fd = client_fd.load(Ordering::SeqCst);
+
+    // 客户端暂不读取，输出缓冲区持续增长直到越过高水位
+    let chunk = vec![1u8; 64 * 1024];
+    let mut total = 0;
+    for _ in 0..64 {
+        server.send(fd, &chunk).expect("Failed to send");
+        total += chunk.len();
+    }
+    assert_eq!(high.load(Ordering::SeqCst), 1);
+    assert_eq!(low.load(Ordering::SeqCst), 0);
+
+    let mut received = vec![0u8; total];
+    client.read_exact(&mut received).expect("Failed to read");
+    assert!(wait_until(Duration::from_secs(2), || low.load(Ordering::SeqCst) == 1));
+    assert_eq!(high.load(Ordering::SeqCst), 1);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_close_after_flush() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18087).expect("Failed to create server");
+    let client_fd = track_client_fd(&mut server);
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18087").expect("Failed to connect");
+    client.write_all(b"go").expect("Failed to write");
+    assert!(wait_until(Duration::from_secs(2), || client_fd.load(Ordering::SeqCst) != -1));
+    let fd = client_fd.load(Ordering::SeqCst);
+
+    let payload = vec![9u8; 4 * 1024 * 1024];
+    server.send(fd, &payload).expect("Failed to send");
+    server.close_after_flush(fd).expect("Failed to close after flush");
+    assert!(server.send(fd, b"late").is_err());
+
+    // 读到 EOF 时应恰好收到全部数据
+    let mut received = Vec::new();
+    client.read_to_end(&mut received).expect("Failed to read");
+    assert_eq!(received.len(), payload.len());
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_pause_and_resume_read() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18088).expect("Failed to create server");
+
+    let client_fd = Arc::new(AtomicI32::new(-1));
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client_fd_clone = Arc::clone(&client_fd);
+    let received_clone = Arc::clone(&received);
+    server.set_receive_handler(move |fd, data, _len| {
+        client_fd_clone.store(fd, Ordering::SeqCst);
+        received_clone.lock().unwrap().extend_from_slice(data);
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18088").expect("Failed to connect");
+    client.write_all(b"first").expect("Failed to write");
+    assert!(wait_until(Duration::from_secs(2), || client_fd.load(Ordering::SeqCst) != -1));
+    let fd = client_fd.load(Ordering::SeqCst);
+
+    server.pause_read(fd).expect("Failed to pause read");
+    thread::sleep(Duration::from_millis(50));
+    client.write_all(b"second").expect("Failed to write");
+
+    // 暂停期间数据留在内核缓冲区中
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(*received.lock().unwrap(), b"first");
+
+    server.resume_read(fd).expect("Failed to resume read");
+    assert!(wait_until(Duration::from_secs(2), || *received.lock().unwrap() == b"firstsecond"));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_peer_addr() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18092).expect("Failed to create server");
+    let client_fd = track_client_fd(&mut server);
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18092").expect("Failed to connect");
+    client.write_all(b"addr").expect("Failed to write");
+    assert!(wait_until(Duration::from_secs(2), || client_fd.load(Ordering::SeqCst) != -1));
+
+    // 服务端看到的对端地址就是客户端的本地地址
+    let fd = client_fd.load(Ordering::SeqCst);
+    assert_eq!(server.peer_addr(fd), Some(client.local_addr().unwrap()));
+    assert_eq!(server.peer_addr(-1), None);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+// 记录所有连接的对端地址
+fn track_peers(server: &mut TcpServer) -> Arc<Mutex<Vec<std::net::SocketAddr>>> {
+    let peers = Arc::new(Mutex::new(Vec::new()));
+    let peers_clone = Arc::clone(&peers);
+    server.set_connect_handler(move |conn| {
+        peers_clone.lock().unwrap().extend(conn.peer_addr());
+    });
+    peers
+}
+
+#[test]
+fn test_ipv6_listener() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "::1", 18093).expect("Failed to create server");
+    assert_eq!(server.local_addr().unwrap().ip(), IpAddr::V6(Ipv6Addr::LOCALHOST));
+    let peers = track_peers(&mut server);
+    let (mut server, server_thread) = start_server(server);
+
+    let client = TcpStream::connect("[::1]:18093").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || peers.lock().unwrap().len() == 1));
+    assert_eq!(peers.lock().unwrap()[0], client.local_addr().unwrap());
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_dual_stack_listener() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let options = ListenOptions { v6_only: Some(false), ..ListenOptions::default() };
+    let mut server = TcpServer::with_options(reactor, "[::]", 18094, options).expect("Failed to create server");
+    let peers = track_peers(&mut server);
+    let (mut server, server_thread) = start_server(server);
+
+    // IPv4 客户端的映射地址应还原为 IPv4
+    let _v4 = TcpStream::connect("127.0.0.1:18094").expect("Failed to connect over IPv4");
+    assert!(wait_until(Duration::from_secs(2), || peers.lock().unwrap().len() == 1));
+    let _v6 = TcpStream::connect("[::1]:18094").expect("Failed to connect over IPv6");
+    assert!(wait_until(Duration::from_secs(2), || peers.lock().unwrap().len() == 2));
+
+    let peers = peers.lock().unwrap();
+    assert_eq!(peers[0].ip(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+    assert_eq!(peers[1].ip(), IpAddr::V6(Ipv6Addr::LOCALHOST));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_v6_only_listener() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let options = ListenOptions { v6_only: Some(true), ..ListenOptions::default() };
+    let mut server = TcpServer::with_options(reactor, "::", 18095, options).expect("Failed to create server");
+    let peers = track_peers(&mut server);
+    let (mut server, server_thread) = start_server(server);
+
+    let _v6 = TcpStream::connect("[::1]:18095").expect("Failed to connect over IPv6");
+    assert!(TcpStream::connect("127.0.0.1:18095").is_err());
+    assert!(wait_until(Duration::from_secs(2), || peers.lock().unwrap().len() == 1));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_reuse_port_listeners() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let options = ListenOptions { reuse_port: true, ..ListenOptions::default() };
+    let mut server = TcpServer::with_options(reactor, "127.0.0.1", 18100, options).expect("Failed to create server");
+    server.set_thread_num(4);
+
+    // 记录每个连接被哪个 IO 线程接受
+    let threads = Arc::new(Mutex::new(Vec::new()));
+    let threads_clone = Arc::clone(&threads);
+    server.set_connect_handler(move |_conn| {
+        let name = thread::current().name().unwrap_or("").to_string();
+        threads_clone.lock().unwrap().push(name);
+    });
+    server.set_message_handler(|conn, data| {
+        conn.send(data).expect("Failed to echo");
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let mut clients = Vec::new();
+    for i in 0..64u8 {
+        let mut client = TcpStream::connect("127.0.0.1:18100").expect("Failed to connect");
+        client.write_all(&[i]).expect("Failed to write");
+        let mut buf = [0u8; 1];
+        client.read_exact(&mut buf).expect("Failed to read");
+        assert_eq!(buf[0], i);
+        clients.push(client);
+    }
+
+    // 新连接由内核分发到多个监听套接字，主 reactor 不参与 accept
+    let threads = threads.lock().unwrap();
+    assert_eq!(threads.len(), 64);
+    assert!(threads.iter().all(|name| name.starts_with("reactor-")));
+    assert!(threads.iter().collect::<HashSet<_>>().len() > 1);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_reuse_port_shutdown_closes_all_listeners() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let options = ListenOptions { reuse_port: true, ..ListenOptions::default() };
+    let mut server = TcpServer::with_options(reactor, "127.0.0.1", 18121, options).expect("Failed to create server");
+    server.set_thread_num(4);
+    let (server, server_thread) = start_server(server);
+
+    // 保持一个连接不关闭，让排空持续到宽限期结束
+    let _client = TcpStream::connect("127.0.0.1:18121").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || server.connection_count() == 1));
+    let shutdown_thread = thread::spawn(move || {
+        let mut server = server;
+        server.shutdown(Duration::from_millis(500)).expect("Failed to shutdown");
+        server
+    });
+
+    // 宽限期内每个监听套接字都已关闭，新连接无论分到哪个都被拒绝
+    thread::sleep(Duration::from_millis(100));
+    for _ in 0..32 {
+        assert!(TcpStream::connect("127.0.0.1:18121").is_err());
+    }
+
+    let _server = shutdown_thread.join().unwrap();
+    server_thread.join().unwrap();
+}
+
+// 被拒绝的连接会立即被服务端关闭
+fn assert_rejected(client: &mut TcpStream) {
+    client.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let mut buf = [0u8; 1];
+    match client.read(&mut buf) {
+        Ok(n) => assert_eq!(n, 0),
+        Err(e) => assert_eq!(e.kind(), std::io::ErrorKind::ConnectionReset),
+    }
+}
+
+#[test]
+fn test_max_connections() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18101).expect("Failed to create server");
+    server.set_max_connections(2);
+    let (mut server, server_thread) = start_server(server);
+
+    let first = TcpStream::connect("127.0.0.1:18101").expect("Failed to connect");
+    let _second = TcpStream::connect("127.0.0.1:18101").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || server.connection_count() == 2));
+
+    let mut third = TcpStream::connect("127.0.0.1:18101").expect("Failed to connect");
+    assert_rejected(&mut third);
+    assert_eq!(server.connection_count(), 2);
+
+    // 有连接断开后可以重新接入
+    drop(first);
+    assert!(wait_until(Duration::from_secs(2), || server.connection_count() == 1));
+    let _fourth = TcpStream::connect("127.0.0.1:18101").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || server.connection_count() == 2));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_max_connections_per_ip() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18102).expect("Failed to create server");
+    server.set_max_connections_per_ip(1);
+    server.set_thread_num(2);
+    let (mut server, server_thread) = start_server(server);
+
+    let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let first = TcpStream::connect("127.0.0.1:18102").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || server.connections_from(localhost) == 1));
+
+    let mut second = TcpStream::connect("127.0.0.1:18102").expect("Failed to connect");
+    assert_rejected(&mut second);
+    assert_eq!(server.connections_from(localhost), 1);
+
+    drop(first);
+    assert!(wait_until(Duration::from_secs(2), || server.connections_from(localhost) == 0));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_groups_and_broadcast() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18115).expect("Failed to create server");
+    server.set_thread_num(2);
+    // 每个连接加入聊天室，收到的消息转发给其他成员
+    server.set_connect_handler(|conn| {
+        conn.join_group("room").expect("Failed to join");
+        conn.join_group("all").expect("Failed to join");
+    });
+    server.set_message_handler(|conn, data| {
+        conn.broadcast("room", data);
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let mut clients: Vec<TcpStream> = (0..3)
+        .map(|_| TcpStream::connect("127.0.0.1:18115").expect("Failed to connect"))
+        .collect();
+    assert!(wait_until(Duration::from_secs(2), || server.group_size("room") == 3));
+
+    clients[0].write_all(b"hi").expect("Failed to write");
+    for client in &mut clients[1..] {
+        let mut buf = [0u8; 2];
+        client.read_exact(&mut buf).expect("Failed to read");
+        assert_eq!(&buf, b"hi");
+    }
+
+    // 大块数据写不完时各连接引用同一块缓冲区
+    let payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 253) as u8).collect();
+    assert_eq!(server.broadcast("all", &payload), 3);
+    for client in &mut clients {
+        let mut received = vec![0u8; payload.len()];
+        client.read_exact(&mut received).expect("Failed to read");
+        assert!(received == payload);
+    }
+
+    // 发送者自己没有收到聊天消息
+    clients[0].set_read_timeout(Some(Duration::from_millis(50))).unwrap();
+    assert!(clients[0].read(&mut [0u8; 1]).is_err());
+
+    // 关闭的连接自动离开所有分组，离开最后一个成员后分组被删除
+    drop(clients.pop());
+    assert!(wait_until(Duration::from_secs(2), || server.group_size("room") == 2));
+    assert_eq!(server.group_size("all"), 2);
+    assert_eq!(server.broadcast("missing", b"x"), 0);
+    drop(clients);
+    assert!(wait_until(Duration::from_secs(2), || server.group_size("all") == 0));
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_join_and_leave_group() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18116).expect("Failed to create server");
+    let accepted = Arc::new(Mutex::new(None));
+    let accepted_clone = Arc::clone(&accepted);
+    server.set_connect_handler(move |conn| {
+        *accepted_clone.lock().unwrap() = Some(conn.clone());
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18116").expect("Failed to connect");
+    assert!(wait_until(Duration::from_secs(2), || accepted.lock().unwrap().is_some()));
+    let conn = accepted.lock().unwrap().take().unwrap();
+
+    server.join_group(conn.fd(), "a").unwrap();
+    conn.join_group("b").unwrap();
+    let mut groups = conn.groups();
+    groups.sort();
+    assert_eq!(groups, vec!["a", "b"]);
+
+    assert!(server.leave_group(conn.fd(), "a").unwrap());
+    assert!(!conn.leave_group("a"));
+    assert_eq!(server.group_size("a"), 0);
+    assert_eq!(server.broadcast("b", b"only b"), 1);
+    let mut buf = [0u8; 6];
+    client.read_exact(&mut buf).expect("Failed to read");
+    assert_eq!(&buf, b"only b");
+
+    // 已关闭的连接不能再加入分组
+    conn.close();
+    assert!(conn.join_group("c").is_err());
+    assert_eq!(server.group_size("b"), 0);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_send_file() {
+    let path = std::env::temp_dir().join(format!("tinyserver_send_file_{}", std::process::id()));
+    let content: Vec<u8> = (0..8 * 1024 * 1024).map(|i| (i % 241) as u8).collect();
+    std::fs::write(&path, &content).expect("Failed to write file");
+
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18117).expect("Failed to create server");
+    let file_path = path.clone();
+    let file_len = content.len() as u64;
+    // 文件数据和普通数据按调用顺序发送
+    server.set_connect_handler(move |conn| {
+        let file = std::fs::File::open(&file_path).expect("Failed to open file");
+        let invalid = file.try_clone().unwrap();
+        assert!(conn.send_file(invalid, file_len - 10, 11).is_err());
+
+        conn.send(b"HEAD").expect("Failed to send");
+        conn.send_file(file, 100, file_len - 200).expect("Failed to send file");
+        conn.send(b"TAIL").expect("Failed to send");
+        conn.close_after_flush();
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18117").expect("Failed to connect");
+    let mut received = Vec::new();
+    client.read_to_end(&mut received).expect("Failed to read");
+
+    let mut expected = b"HEAD".to_vec();
+    expected.extend_from_slice(&content[100..content.len() - 100]);
+    expected.extend_from_slice(b"TAIL");
+    assert_eq!(received.len(), expected.len());
+    assert!(received == expected);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_send_vectored() {
+    let body: Arc<Vec<u8>> = Arc::new((0..8 * 1024 * 1024).map(|i| (i % 239) as u8).collect());
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", 18119).expect("Failed to create server");
+    let response = Arc::clone(&body);
+    // 正文远超 socket 缓冲区，第一次 writev 只能写出一部分，第二次调用排在缓冲区之后
+    server.set_connect_handler(move |conn| {
+        conn.send_vectored(&[IoSlice::new(b"HEAD"), IoSlice::new(b""), IoSlice::new(&response), IoSlice::new(b"TAIL")])
+            .expect("Failed to send");
+        assert!(conn.pending_bytes() > 0);
+        conn.send_vectored(&[IoSlice::new(b"NEXT"), IoSlice::new(b"END")]).expect("Failed to send");
+        conn.close_after_flush();
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let mut client = TcpStream::connect("127.0.0.1:18119").expect("Failed to connect");
+    let mut received = Vec::new();
+    client.read_to_end(&mut received).expect("Failed to read");
+
+    let mut expected = b"HEAD".to_vec();
+    expected.extend_from_slice(&body);
+    expected.extend_from_slice(b"TAILNEXTEND");
+    assert_eq!(received.len(), expected.len());
+    assert!(received == expected);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_stop_closes_just_accepted_connections() {
+    // 连接刚被 accept、注册任务尚未执行时 stop，客户端也必须收到 EOF 或 RST
+    for i in 0..100 {
+        let reactor = Reactor::new().expect("Failed to create reactor");
+        let mut server = TcpServer::new(reactor, "127.0.0.1", 18120).expect("Failed to create server");
+        server.set_thread_num(2);
+        let (mut server, server_thread) = start_server(server);
+
+        let mut client = TcpStream::connect("127.0.0.1:18120").expect("Failed to connect");
+        // 错开 stop 的时机，覆盖 accept 前后和注册任务执行前后的各种交错
+        thread::sleep(Duration::from_micros(i * 20));
+        server.stop().expect("Failed to stop server");
+        server_thread.join().unwrap();
+        drop(server);
+
+        client.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let mut buf = [0u8; 16];
+        match client.read(&mut buf) {
+            Ok(n) => assert_eq!(n, 0),
+            Err(e) => assert!(
+                !matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut),
+                "connection left open after stop"
+            ),
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::common::{start_server, wait_until};
+use rust_version::core::reactor::Reactor;
+use rust_version::network::connection::CloseReason;
+use rust_version::network::tcp_server::TcpServer;
+use rust_version::network::tls::{self, CertResolver};
+use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls").join(name)
+}
+
+fn load_cert(name: &str) -> CertificateDer<'static> {
+    let mut reader = BufReader::new(File::open(fixture(name)).unwrap());
+    let cert = rustls_pemfile::certs(&mut reader).next().unwrap().unwrap();
+    cert
+}
+
+// 自签名证书直接作为信任根
+fn connect(port: u16, server_name: &str, trusted: &str) -> StreamOwned<ClientConnection, TcpStream> {
+    let mut roots = RootCertStore::empty();
+    roots.add(load_cert(trusted)).unwrap();
+    let config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let name = ServerName::try_from(server_name.to_string()).unwrap();
+    let conn = ClientConnection::new(Arc::new(config), name).unwrap();
+    let sock = TcpStream::connect(("127.0.0.1", port)).expect("Failed to connect");
+    StreamOwned::new(conn, sock)
+}
+
+type Events = Arc<Mutex<Vec<String>>>;
+
+fn start_tls_server(port: u16, config: Arc<rustls::ServerConfig>) -> (TcpServer, thread::JoinHandle<()>, Events) {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = TcpServer::new(reactor, "127.0.0.1", port).expect("Failed to create server");
+    server.set_thread_num(1);
+    server.set_tls_config(config);
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let connect_events = Arc::clone(&events);
+    let close_events = Arc::clone(&events);
+    server.set_connect_handler(move |conn| {
+        let name = conn.tls_server_name().unwrap_or_default();
+        connect_events.lock().unwrap().push(format!("connect {}", name));
+    });
+    server.set_message_handler(|conn, data| {
+        conn.send(data).expect("Failed to echo");
+    });
+    server.set_close_handler(move |_conn, reason| {
+        close_events.lock().unwrap().push(format!("close {:?}", reason));
+    });
+
+    let (server, server_thread) = start_server(server);
+    (server, server_thread, events)
+}
+
+#[test]
+fn test_tls_echo() {
+    let config = tls::server_config_from_pem(fixture("localhost.crt"), fixture("localhost.key")).unwrap();
+    let (mut server, server_thread, events) = start_tls_server(18108, config);
+
+    let mut client = connect(18108, "localhost", "localhost.crt");
+    client.write_all(b"hello").expect("Failed to write");
+    let mut echoed = [0u8; 5];
+    client.read_exact(&mut echoed).expect("Failed to read");
+    assert_eq!(&echoed, b"hello");
+
+    // 大块数据会分成多个 TLS 记录
+    let payload: Vec<u8> = (0..256 * 1024).map(|i| (i % 251) as u8).collect();
+    client.write_all(&payload).expect("Failed to write");
+    let mut echoed = vec![0u8; payload.len()];
+    client.read_exact(&mut echoed).expect("Failed to read");
+    assert_eq!(echoed, payload);
+
+    // 客户端发送 close_notify 后关闭
+    client.conn.send_close_notify();
+    client.flush().expect("Failed to flush");
+    assert!(wait_until(Duration::from_secs(2), || events.lock().unwrap().len() == 2));
+    assert_eq!(*events.lock().unwrap(), vec!["connect localhost", "close PeerEof"]);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_tls_sni_certificate_selection() {
+    let mut resolver = CertResolver::new();
+    resolver.set_default(fixture("localhost.crt"), fixture("localhost.key")).unwrap();
+    resolver.add("example.test", fixture("example.test.crt"), fixture("example.test.key")).unwrap();
+    let config = tls::server_config(resolver).unwrap();
+    let (mut server, server_thread, events) = start_tls_server(18109, config);
+
+    for name in ["example.test", "localhost"] {
+        let cert = format!("{}.crt", name);
+        let mut client = connect(18109, name, &cert);
+        client.write_all(b"ping").expect("Failed to write");
+        let mut echoed = [0u8; 4];
+        client.read_exact(&mut echoed).expect("Failed to read");
+        assert_eq!(client.conn.peer_certificates().unwrap()[0], load_cert(&cert));
+    }
+
+    // 只信任 example.test 证书时，请求 localhost 会拿到不被信任的默认证书
+    let mut client = connect(18109, "localhost", "example.test.crt");
+    assert!(client.write_all(b"ping").is_err());
+
+    assert!(wait_until(Duration::from_secs(2), || events.lock().unwrap().len() >= 4));
+    let events = events.lock().unwrap().clone();
+    assert_eq!(&events[..2], ["connect example.test", "close PeerEof"]);
+    assert_eq!(&events[2..4], ["connect localhost", "close PeerEof"]);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_tls_rejects_plaintext() {
+    let config = tls::server_config_from_pem(fixture("localhost.crt"), fixture("localhost.key")).unwrap();
+    let (mut server, server_thread, events) = start_tls_server(18110, config);
+
+    // 非 TLS 数据导致握手失败，连接被关闭且不会通知 connect
+    let mut client = TcpStream::connect("127.0.0.1:18110").expect("Failed to connect");
+    client.write_all(b"GET / HTTP/1.1\r\n\r\n").expect("Failed to write");
+    let mut buf = Vec::new();
+    let _ = client.read_to_end(&mut buf);
+
+    assert!(wait_until(Duration::from_secs(2), || events.lock().unwrap().len() == 1));
+    assert_eq!(
+        events.lock().unwrap()[0],
+        format!("close {:?}", CloseReason::Error(std::io::ErrorKind::InvalidData))
+    );
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_tls_send_file() {
+    let path = std::env::temp_dir().join(format!("tinyserver_tls_send_file_{}", std::process::id()));
+    let content: Vec<u8> = (0..300 * 1024).map(|i| (i % 239) as u8).collect();
+    std::fs::write(&path, &content).expect("Failed to write file");
+
+    let config = tls::server_config_from_pem(fixture("localhost.crt"), fixture("localhost.key")).unwrap();
+    let (mut server, server_thread, _events) = start_tls_server(18118, config);
+
+    // TLS 连接无法使用 sendfile，文件内容加密后发送
+    let file_path = path.clone();
+    let file_len = content.len() as u64;
+    server.set_message_handler(move |conn, _data| {
+        let file = File::open(&file_path).unwrap();
+        conn.send_file(file, 10, file_len - 10).expect("Failed to send file");
+    });
+    let mut client = connect(18118, "localhost", "localhost.crt");
+    client.write_all(b"x").expect("Failed to write");
+
+    let mut received = vec![0u8; content.len() - 10];
+    client.read_exact(&mut received).expect("Failed to read");
+    assert!(received[..] == content[10..]);
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
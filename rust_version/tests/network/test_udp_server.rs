@@ -0,0 +1,67 @@
+use crate::common::{start_server, wait_until};
+use rust_version::core::reactor::Reactor;
+use rust_version::network::udp_server::UdpServer;
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn test_udp_echo() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = UdpServer::new(reactor, "127.0.0.1", 18096).expect("Failed to create server");
+    let sender = server.sender();
+    server.set_receive_handler(move |peer, data| {
+        sender.send_to(peer, data).expect("Failed to echo");
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    let client = UdpSocket::bind("127.0.0.1:0").expect("Failed to bind client");
+    client.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    client.send_to(b"telemetry", "127.0.0.1:18096").expect("Failed to send");
+
+    let mut buf = [0u8; 64];
+    let (len, from) = client.recv_from(&mut buf).expect("Failed to receive echo");
+    assert_eq!(&buf[..len], b"telemetry");
+    assert_eq!(from, server.local_addr().unwrap());
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_udp_batches() {
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = UdpServer::new(reactor, "127.0.0.1", 18097).expect("Failed to create server");
+    server.set_batch_size(4);
+
+    let received = Arc::new(AtomicUsize::new(0));
+    let received_clone = Arc::clone(&received);
+    server.set_receive_handler(move |_peer, data| {
+        assert_eq!(data.len(), 8);
+        received_clone.fetch_add(1, Ordering::SeqCst);
+    });
+    let (mut server, server_thread) = start_server(server);
+
+    // 数据报个数不是批大小的整数倍
+    let client = UdpSocket::bind("127.0.0.1:0").expect("Failed to bind client");
+    for i in 0..42u64 {
+        client.send_to(&i.to_le_bytes(), "127.0.0.1:18097").expect("Failed to send");
+    }
+    assert!(wait_until(Duration::from_secs(2), || received.load(Ordering::SeqCst) == 42));
+
+    // sendmmsg 一次发出多个数据报
+    let peer = client.local_addr().unwrap();
+    let datagrams: Vec<(std::net::SocketAddr, &[u8])> = vec![(peer, b"a"), (peer, b"bb"), (peer, b"ccc")];
+    assert_eq!(server.send_batch(&datagrams).expect("Failed to send batch"), 3);
+
+    client.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let mut buf = [0u8; 16];
+    for expected in [&b"a"[..], b"bb", b"ccc"] {
+        let (len, _) = client.recv_from(&mut buf).expect("Failed to receive");
+        assert_eq!(&buf[..len], expected);
+    }
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}
@@ -0,0 +1,68 @@
+use crate::common::start_server;
+use rust_version::core::reactor::Reactor;
+use rust_version::network::unix_server::UnixServer;
+use std::io::{Read, Write};
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixListener, UnixStream};
+use std::path::PathBuf;
+
+fn socket_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("tinyserver-{}-{}.sock", name, std::process::id()))
+}
+
+fn echo(server: &mut UnixServer) {
+    server.set_message_handler(|conn, data| {
+        assert!(conn.peer_addr().is_none());
+        conn.send(data).expect("Failed to echo");
+    });
+}
+
+#[test]
+fn test_unix_server_echo_and_cleanup() {
+    let path = socket_path("echo");
+
+    // 模拟上次进程遗留的套接字文件
+    drop(UnixListener::bind(&path).expect("Failed to create stale socket"));
+    assert!(path.exists());
+
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = UnixServer::bind(reactor, &path).expect("Failed to replace stale socket");
+    assert_eq!(server.socket_path(), Some(path.as_path()));
+    echo(&mut server);
+    let (mut server, server_thread) = start_server(server);
+
+    // 正在监听的套接字不能被抢占
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    assert!(UnixServer::bind(reactor, &path).is_err());
+
+    let mut client = UnixStream::connect(&path).expect("Failed to connect");
+    client.write_all(b"ping").expect("Failed to write");
+    let mut buf = [0u8; 4];
+    client.read_exact(&mut buf).expect("Failed to read");
+    assert_eq!(&buf, b"ping");
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+    drop(server);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_unix_server_abstract_namespace() {
+    let name = format!("tinyserver-abstract-{}", std::process::id());
+    let reactor = Reactor::new().expect("Failed to create reactor");
+    let mut server = UnixServer::bind_abstract(reactor, name.as_bytes()).expect("Failed to bind");
+    assert!(server.socket_path().is_none());
+    echo(&mut server);
+    let (mut server, server_thread) = start_server(server);
+
+    let addr = SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+    let mut client = UnixStream::connect_addr(&addr).expect("Failed to connect");
+    client.write_all(b"abstract").expect("Failed to write");
+    let mut buf = [0u8; 8];
+    client.read_exact(&mut buf).expect("Failed to read");
+    assert_eq!(&buf, b"abstract");
+
+    server.stop().expect("Failed to stop server");
+    server_thread.join().unwrap();
+}